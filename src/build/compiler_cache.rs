@@ -0,0 +1,90 @@
+use std::{path::Path, process::{Command, Stdio}};
+
+use crate::toolchain::env;
+
+// A compiler cache launching the compiler, as in `ccache g++`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilerCache {
+    Ccache,
+    Sccache,
+}
+
+impl CompilerCache {
+    pub fn detect(compiler: &str) -> Option<CompilerCache> {
+        let words = env::split_flags(compiler);
+        let stem = Path::new(words.first()?).file_stem()?.to_string_lossy().to_lowercase();
+
+        match stem.as_str() {
+            "ccache" => Some(CompilerCache::Ccache),
+            "sccache" => Some(CompilerCache::Sccache),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompilerCache::Ccache => "ccache",
+            CompilerCache::Sccache => "sccache",
+        }
+    }
+
+    /// The cache hits counted by the cache so far. The hits of a build are
+    /// the difference of the counts before and after it, which includes
+    /// the hits of other builds using the same cache at the same time.
+    pub fn hit_count(&self) -> Option<u64> {
+        let args: &[&str] = match self {
+            CompilerCache::Ccache => &["--print-stats"],
+            CompilerCache::Sccache => &["--show-stats", "--stats-format=json"],
+        };
+
+        let output = Command::new(self.name())
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let stats = String::from_utf8_lossy(&output.stdout);
+        match self {
+            CompilerCache::Ccache => parse_ccache_stats(&stats),
+            CompilerCache::Sccache => parse_sccache_stats(&stats),
+        }
+    }
+}
+
+// `ccache --print-stats` prints a tab separated counter per line
+fn parse_ccache_stats(stats: &str) -> Option<u64> {
+    let counters: Vec<u64> = stats
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(name, _)| matches!(*name, "direct_cache_hit" | "preprocessed_cache_hit"))
+        .filter_map(|(_, value)| value.trim().parse().ok())
+        .collect();
+
+    (!counters.is_empty()).then(|| counters.iter().sum())
+}
+
+// the hits of every language in `stats.cache_hits.counts`
+fn parse_sccache_stats(stats: &str) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_str(stats).ok()?;
+    let counts = value.get("stats")?.get("cache_hits")?.get("counts")?.as_object()?;
+
+    Some(counts.values().filter_map(|count| count.as_u64()).sum())
+}
+
+#[test]
+fn test_compiler_cache() {
+    assert_eq!(CompilerCache::detect("ccache g++"), Some(CompilerCache::Ccache));
+    assert_eq!(CompilerCache::detect("/usr/bin/sccache clang++"), Some(CompilerCache::Sccache));
+    assert_eq!(CompilerCache::detect("g++"), None);
+
+    let stats = "stats_updated_timestamp\t1700000000\ndirect_cache_hit\t12\npreprocessed_cache_hit\t3\ncache_miss\t5\n";
+    assert_eq!(parse_ccache_stats(stats), Some(15));
+    assert_eq!(parse_ccache_stats("ccache version 3.7"), None);
+
+    let stats = r#"{"stats": {"cache_hits": {"counts": {"C/C++": 7, "CUDA": 1}}, "cache_misses": {"counts": {"C/C++": 2}}}}"#;
+    assert_eq!(parse_sccache_stats(stats), Some(8));
+}
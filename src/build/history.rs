@@ -0,0 +1,61 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::{LazyLock, Mutex}, time::Duration};
+
+use crate::utils::get_cxon_cache_dir;
+
+const COMPILE_TIMES_FILE: &str = "compile_times.json";
+
+// compile duration of every source in milliseconds, loaded from the previous build
+static COMPILE_TIMES: LazyLock<Mutex<HashMap<PathBuf, u64>>> = LazyLock::new(|| {
+    let path = get_cxon_cache_dir().join(COMPILE_TIMES_FILE);
+
+    let times = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    Mutex::new(times)
+});
+
+pub fn get_compile_time(src: &Path) -> Option<Duration> {
+    COMPILE_TIMES.lock().unwrap().get(src).map(|ms| Duration::from_millis(*ms))
+}
+
+pub fn record_compile_time(src: &Path, duration: Duration) {
+    COMPILE_TIMES.lock().unwrap().insert(src.to_path_buf(), duration.as_millis() as u64);
+}
+
+pub fn save_compile_times() -> Result<(), Box<dyn std::error::Error>> {
+    let times = COMPILE_TIMES.lock().unwrap().clone();
+    let content = serde_json::to_string_pretty(&times)?;
+
+    std::fs::write(get_cxon_cache_dir().join(COMPILE_TIMES_FILE), content)?;
+
+    Ok(())
+}
+
+/// Orders the sources so that the ones which took the longest to compile last
+/// time are popped from the back of the queue first. Sources without a record
+/// are assumed to be slow, since they are usually new files.
+pub fn sort_by_compile_time(sources: &mut [PathBuf]) {
+    sort_by_time(sources, get_compile_time);
+}
+
+fn sort_by_time(sources: &mut [PathBuf], compile_time: impl Fn(&Path) -> Option<Duration>) {
+    sources.sort_by_key(|src| compile_time(src).unwrap_or(Duration::MAX));
+}
+
+#[test]
+fn test_sort_by_compile_time() {
+    let times = HashMap::from([
+        (PathBuf::from("fast.cpp"), Duration::from_millis(100)),
+        (PathBuf::from("slow.cpp"), Duration::from_secs(3)),
+        (PathBuf::from("medium.cpp"), Duration::from_secs(1)),
+    ]);
+    let mut sources: Vec<PathBuf> = ["fast.cpp", "new.cpp", "slow.cpp", "medium.cpp"].map(PathBuf::from).into();
+
+    sort_by_time(&mut sources, |src| times.get(src).copied());
+
+    // popped from the back: the new source first, then the slowest
+    let order: Vec<PathBuf> = std::iter::from_fn(|| sources.pop()).collect();
+    assert_eq!(order, ["new.cpp", "slow.cpp", "medium.cpp", "fast.cpp"].map(PathBuf::from));
+}
@@ -0,0 +1,88 @@
+use std::{process::Command, sync::{LazyLock, atomic::{AtomicBool, Ordering}}};
+
+use jobserver::{Acquired, Client, FromEnvErrorKind};
+
+use crate::cxon::get_cxon_config;
+
+static JOBSERVER: LazyLock<Jobserver> = LazyLock::new(Jobserver::new);
+
+/// The jobserver must be initialized before any other file is opened,
+/// otherwise the pipe descriptors inherited from make may be reused.
+pub fn get_jobserver() -> &'static Jobserver {
+    &JOBSERVER
+}
+
+pub struct Jobserver {
+    client: Client,
+    // every process owns one implicit token that is never written to the pipe
+    implicit_token: AtomicBool,
+    inherited: bool,
+}
+
+// Dropping the token gives the job slot back to the jobserver
+pub struct JobToken {
+    #[allow(dead_code)] acquired: Option<Acquired>,
+}
+
+impl Jobserver {
+    fn new() -> Self {
+        // SAFETY: called once while the process is starting, before cxon opens any file
+        let from_env = unsafe { Client::from_env_ext(true) };
+
+        match from_env.client {
+            Ok(client) => {
+                return Self {
+                    client,
+                    implicit_token: AtomicBool::new(true),
+                    inherited: true,
+                };
+            }
+            Err(err) => match err.kind() {
+                FromEnvErrorKind::NoEnvVar | FromEnvErrorKind::NoJobserver => {}
+                _ => eprintln!("Warning: ignoring the jobserver from the environment: {}", err),
+            },
+        }
+
+        let threads = get_cxon_config().read().unwrap().get_threads();
+        let client = Client::new(threads.saturating_sub(1))
+            .expect("Failed to create the jobserver");
+
+        Self {
+            client,
+            implicit_token: AtomicBool::new(true),
+            inherited: false,
+        }
+    }
+
+    // true if cxon runs under a parent make and shares its job slots
+    pub fn is_inherited(&self) -> bool {
+        self.inherited
+    }
+
+    /// Blocks until a job slot is available.
+    pub fn acquire(&self) -> JobToken {
+        if self.implicit_token.swap(false, Ordering::AcqRel) {
+            return JobToken { acquired: None };
+        }
+
+        let acquired = self.client
+            .acquire()
+            .expect("Failed to acquire a token from the jobserver");
+
+        JobToken { acquired: Some(acquired) }
+    }
+
+    /// Passes the jobserver down to a child process, so that nested make or
+    /// cxon builds and `-flto=jobserver` share the same job slots.
+    pub fn configure(&self, cmd: &mut Command) {
+        self.client.configure_make(cmd);
+    }
+}
+
+impl Drop for JobToken {
+    fn drop(&mut self) {
+        if self.acquired.is_none() {
+            get_jobserver().implicit_token.store(true, Ordering::Release);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{sync::atomic::{AtomicUsize, Ordering}, thread, time::Duration};
+
+use crate::cxon::get_cxon_config;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+static RUNNING_JOBS: AtomicUsize = AtomicUsize::new(0);
+
+// A running job, which is counted until it is dropped
+pub struct JobSlot;
+
+/// Blocks until the system load and free memory allow another compile job.
+/// A job is always started when nothing else is running, so the build
+/// keeps making progress on an overloaded machine.
+pub fn wait_for_resources() -> JobSlot {
+    let (max_load, memory_per_job) = {
+        let cxon = get_cxon_config().read().unwrap();
+        (cxon.get_max_load(), cxon.memory_per_job)
+    };
+
+    loop {
+        let running = RUNNING_JOBS.load(Ordering::Acquire);
+        if running > 0 && !has_resources(max_load, memory_per_job, get_load_average(), get_available_memory()) {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+
+        // another thread may have started a job since the count was read, then it's checked again
+        if RUNNING_JOBS.compare_exchange(running, running + 1, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+            return JobSlot;
+        }
+    }
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        RUNNING_JOBS.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// a limit is ignored when the platform doesn't report what it limits
+fn has_resources(max_load: Option<f64>, memory_per_job: Option<u64>, load: Option<f64>, available_memory: Option<u64>) -> bool {
+    if let (Some(max_load), Some(load)) = (max_load, load)
+        && load >= max_load {
+        return false;
+    }
+
+    if let (Some(memory_per_job), Some(available)) = (memory_per_job, available_memory)
+        && available < memory_per_job {
+        return false;
+    }
+
+    true
+}
+
+// 1 minute load average from /proc, None elsewhere such as on macOS and Windows
+fn get_load_average() -> Option<f64> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+// available memory in MiB from /proc, None elsewhere such as on macOS and Windows
+fn get_available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+
+    Some(kib / 1024)
+}
+
+#[test]
+fn test_has_resources() {
+    assert!(has_resources(None, None, Some(100.0), Some(0)));
+
+    assert!(has_resources(Some(8.0), None, Some(7.5), None));
+    assert!(!has_resources(Some(8.0), None, Some(8.0), None));
+    assert!(has_resources(Some(8.0), None, None, None));
+
+    assert!(has_resources(None, Some(2048), None, Some(4096)));
+    assert!(!has_resources(None, Some(2048), None, Some(1024)));
+    assert!(has_resources(None, Some(2048), None, None));
+
+    assert!(!has_resources(Some(8.0), Some(2048), Some(1.0), Some(1024)));
+}
@@ -0,0 +1,125 @@
+use std::{io::{IsTerminal, Write}, path::Path, sync::{LazyLock, Mutex}};
+
+use crate::cli::arg::get_args;
+
+static PROGRESS: LazyLock<Mutex<Progress>> = LazyLock::new(|| {
+    Mutex::new(Progress {
+        total: 0,
+        finished: 0,
+        running: 0,
+        up_to_date: 0,
+        is_tty: std::io::stdout().is_terminal(),
+        line_dirty: false,
+    })
+});
+
+struct Progress {
+    total: usize,
+    finished: usize,
+    running: usize,
+    up_to_date: usize,
+
+    // on a TTY the status is redrawn in place instead of printing a line per job
+    is_tty: bool,
+    line_dirty: bool,
+}
+
+pub fn set_total(total: usize) {
+    PROGRESS.lock().unwrap().total = total;
+}
+
+pub fn start(src: &Path) {
+    let mut progress = PROGRESS.lock().unwrap();
+    progress.running += 1;
+
+    if progress.is_tty {
+        let status = format!("Compiling {}", display_path(src));
+        progress.draw(&status);
+    }
+}
+
+pub fn up_to_date(src: &Path) {
+    let mut progress = PROGRESS.lock().unwrap();
+    progress.finished += 1;
+    progress.up_to_date += 1;
+
+    if progress.is_tty {
+        let status = format!("Up-to-date {}", display_path(src));
+        progress.draw(&status);
+    }
+}
+
+/// Marks a compile job as done and prints the output of the compiler above
+/// the status line. Its diagnostics go to stderr, as the compiler wrote them.
+pub fn finish(src: &Path, stdout: &[u8], stderr: &[u8]) {
+    let mut progress = PROGRESS.lock().unwrap();
+    progress.running -= 1;
+    progress.finished += 1;
+
+    progress.print_output(stdout, stderr);
+
+    let status = format!("Compiled {}", display_path(src));
+    if progress.is_tty {
+        progress.draw(&status);
+    } else {
+        println!("{} {}", progress.counter(), status);
+    }
+}
+
+// a compile job failed, keep the diagnostics on the screen
+pub fn fail(src: &Path, stdout: &[u8], stderr: &[u8]) {
+    let mut progress = PROGRESS.lock().unwrap();
+    progress.running -= 1;
+
+    progress.print_output(stdout, stderr);
+    progress.clear();
+    eprintln!("Failed to compile {}", display_path(src));
+}
+
+// ends the status line after the last compile job
+pub fn done() {
+    let mut progress = PROGRESS.lock().unwrap();
+    if progress.line_dirty {
+        println!();
+        progress.line_dirty = false;
+    }
+}
+
+impl Progress {
+    fn counter(&self) -> String {
+        format!("[{}/{}]", self.finished, self.total)
+    }
+
+    fn draw(&mut self, status: &str) {
+        print!("\r\x1b[K{} {} ({} running, {} up-to-date)", self.counter(), status, self.running, self.up_to_date);
+        std::io::stdout().flush().unwrap();
+        self.line_dirty = true;
+    }
+
+    fn clear(&mut self) {
+        if self.line_dirty {
+            print!("\r\x1b[K");
+            std::io::stdout().flush().unwrap();
+            self.line_dirty = false;
+        }
+    }
+
+    // cl writes its diagnostics to stdout, gcc and clang to stderr
+    fn print_output(&mut self, stdout: &[u8], stderr: &[u8]) {
+        if stdout.is_empty() && stderr.is_empty() {
+            return;
+        }
+
+        self.clear();
+        std::io::stdout().write_all(stdout).unwrap();
+        std::io::stdout().flush().unwrap();
+        std::io::stderr().write_all(stderr).unwrap();
+    }
+}
+
+pub fn display_path(src: &Path) -> String {
+    let project_dir = get_args().project_dir;
+    let path = pathdiff::diff_paths(src, &project_dir).unwrap_or(src.to_path_buf());
+
+    path.to_string_lossy().to_string()
+}
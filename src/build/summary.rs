@@ -0,0 +1,181 @@
+use std::{cmp::Reverse, path::{Path, PathBuf}, sync::{LazyLock, Mutex}, time::Duration};
+
+use serde::Serialize;
+
+use crate::{build::{compiler_cache::CompilerCache, progress::display_path}, cxon::get_cxon_config};
+
+static COMPILE_STATS: LazyLock<Mutex<CompileStats>> = LazyLock::new(|| Mutex::new(CompileStats::default()));
+
+#[derive(Default)]
+struct CompileStats {
+    compiled: Vec<(PathBuf, Duration)>,
+    up_to_date: usize,
+}
+
+#[derive(Serialize)]
+pub struct BuildSummary {
+    pub compiled: usize,
+    // compiled objects taken from ccache or sccache, None without a compiler cache
+    pub cached: Option<u64>,
+    pub compiler_cache: Option<String>,
+    // objects reused from the build cache
+    pub up_to_date: usize,
+
+    // sum of the compile times of all translation units
+    pub total_compile_time: f64,
+    pub compile_wall_time: f64,
+    pub wall_time: f64,
+    // how busy the build threads were while compiling, from 0 to 1
+    pub parallelism_efficiency: f64,
+
+    pub slowest: Vec<SlowTranslationUnit>,
+
+    pub output: PathBuf,
+    pub output_size: u64,
+}
+
+#[derive(Serialize)]
+pub struct SlowTranslationUnit {
+    pub source: String,
+    pub time: f64,
+}
+
+pub fn record_compiled(src: &Path, duration: Duration) {
+    COMPILE_STATS.lock().unwrap().compiled.push((src.to_path_buf(), duration));
+}
+
+pub fn record_up_to_date() {
+    COMPILE_STATS.lock().unwrap().up_to_date += 1;
+}
+
+impl BuildSummary {
+    pub fn new(output: PathBuf, threads: usize, compile_wall_time: Duration, wall_time: Duration, cached: Option<(CompilerCache, u64)>) -> Self {
+        let stats = COMPILE_STATS.lock().unwrap();
+        let top = get_cxon_config().read().unwrap().build_summary_top;
+
+        let total_compile_time: Duration = stats.compiled.iter().map(|(_, duration)| *duration).sum();
+
+        let parallelism_efficiency = parallelism_efficiency(total_compile_time, compile_wall_time, threads);
+
+        let slowest = slowest(&stats.compiled, top)
+            .into_iter()
+            .map(|(src, duration)| SlowTranslationUnit {
+                source: display_path(&src),
+                time: duration.as_secs_f64(),
+            })
+            .collect();
+
+        let output_size = output.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        Self {
+            compiled: stats.compiled.len(),
+            cached: cached.map(|(_, hits)| hits.min(stats.compiled.len() as u64)),
+            compiler_cache: cached.map(|(cache, _)| cache.name().to_string()),
+            up_to_date: stats.up_to_date,
+            total_compile_time: total_compile_time.as_secs_f64(),
+            compile_wall_time: compile_wall_time.as_secs_f64(),
+            wall_time: wall_time.as_secs_f64(),
+            parallelism_efficiency,
+            slowest,
+            output,
+            output_size,
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Build summary:");
+        match (self.cached, &self.compiler_cache) {
+            (Some(cached), Some(cache)) => println!("  {} compiled ({} cached by {}), {} up-to-date",
+                self.compiled, cached, cache, self.up_to_date),
+            _ => println!("  {} compiled, {} up-to-date", self.compiled, self.up_to_date),
+        }
+        println!("  compile time {:.2}s, wall time {:.2}s, parallelism efficiency {:.0}%",
+            self.total_compile_time, self.wall_time, self.parallelism_efficiency * 100.0);
+
+        if !self.slowest.is_empty() {
+            println!("  slowest translation units:");
+            for unit in &self.slowest {
+                println!("    {:>8.2}s {}", unit.time, unit.source);
+            }
+        }
+
+        println!("  output {} ({})", display_path(&self.output), format_size(self.output_size));
+    }
+
+    pub fn export(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = match get_cxon_config().read().unwrap().export_build_summary_path.clone() {
+            Some(path) if path.extension().is_some_and(|ext| ext == "json") => path,
+            Some(path) => path.join("build_summary.json"),
+            // default path is `build_dir`/build_summary.json
+            None => get_cxon_config()
+                .read()
+                .unwrap()
+                .build_dir
+                .join("build_summary.json"),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
+
+// clamped to 1, as the compile times and the wall time are measured separately
+fn parallelism_efficiency(total_compile_time: Duration, compile_wall_time: Duration, threads: usize) -> f64 {
+    let busy_time = compile_wall_time.as_secs_f64() * threads as f64;
+    if busy_time > 0.0 {
+        (total_compile_time.as_secs_f64() / busy_time).min(1.0)
+    } else {
+        0.0
+    }
+}
+
+fn slowest(compiled: &[(PathBuf, Duration)], top: usize) -> Vec<(PathBuf, Duration)> {
+    let mut compiled = compiled.to_vec();
+    compiled.sort_by_key(|(_, duration)| Reverse(*duration));
+    compiled.truncate(top);
+
+    compiled
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[test]
+fn test_build_summary() {
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(33_587), "32.8 KiB");
+    assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    assert_eq!(format_size(3 * 1024 * 1024 * 1024 * 1024), "3072.0 GiB");
+
+    let second = Duration::from_secs(1);
+    assert_eq!(parallelism_efficiency(second * 6, second * 2, 4), 0.75);
+    assert_eq!(parallelism_efficiency(second * 10, second * 2, 4), 1.0);
+    assert_eq!(parallelism_efficiency(Duration::ZERO, Duration::ZERO, 4), 0.0);
+
+    let compiled = [
+        (PathBuf::from("fast.cpp"), Duration::from_millis(100)),
+        (PathBuf::from("slow.cpp"), Duration::from_secs(3)),
+        (PathBuf::from("medium.cpp"), Duration::from_secs(1)),
+    ];
+    let names: Vec<PathBuf> = slowest(&compiled, 2).into_iter().map(|(src, _)| src).collect();
+    assert_eq!(names, ["slow.cpp", "medium.cpp"].map(PathBuf::from));
+    assert_eq!(slowest(&compiled, 5).len(), 3);
+}
@@ -0,0 +1,164 @@
+use std::{cell::Cell, path::Path, sync::{LazyLock, Mutex}, time::Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::utils::get_cxon_cache_dir;
+
+const TRACE_FILE: &str = "trace.json";
+
+static TRACE_START: LazyLock<Instant> = LazyLock::new(Instant::now);
+static TRACE_EVENTS: LazyLock<Mutex<Vec<Value>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+thread_local! {
+    // lane of the current thread in the trace, 0 is the main thread
+    static LANE: Cell<usize> = const { Cell::new(0) };
+}
+
+// Complete event of the Chrome trace-event format, times are in microseconds
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: String,
+    ph: &'static str,
+    ts: u128,
+    dur: u128,
+    pid: u32,
+    tid: usize,
+}
+
+/// Records the span until it is dropped.
+pub struct Span {
+    name: String,
+    cat: &'static str,
+    start: Instant,
+}
+
+// starts the clock of the trace, everything is measured relative to it
+pub fn init() {
+    LazyLock::force(&TRACE_START);
+}
+
+pub fn start_time() -> Instant {
+    *TRACE_START
+}
+
+pub fn set_lane(lane: usize) {
+    LANE.with(|l| l.set(lane));
+}
+
+pub fn span(name: &str, cat: &'static str) -> Span {
+    Span {
+        name: name.to_string(),
+        cat,
+        start: Instant::now(),
+    }
+}
+
+pub fn record(name: &str, cat: &'static str, start: Instant, end: Instant) {
+    let event = TraceEvent {
+        name: name.to_string(),
+        cat: cat.to_string(),
+        ph: "X",
+        ts: start.saturating_duration_since(*TRACE_START).as_micros(),
+        dur: end.saturating_duration_since(start).as_micros(),
+        pid: std::process::id(),
+        tid: LANE.with(|l| l.get()),
+    };
+
+    TRACE_EVENTS.lock().unwrap().push(serde_json::to_value(event).unwrap());
+}
+
+/// Merges the trace written by clang's `-ftime-trace` into the lane of the
+/// current thread. `start` is the time the compiler was spawned.
+pub fn merge_time_trace(path: &Path, start: Instant) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        eprintln!("Warning: no time trace found at {}", path.display());
+        return;
+    };
+
+    let Ok(trace) = serde_json::from_str::<Value>(&content) else {
+        eprintln!("Warning: failed to parse the time trace {}", path.display());
+        return;
+    };
+
+    let offset = start.saturating_duration_since(*TRACE_START).as_micros() as u64;
+    let lane = LANE.with(|l| l.get());
+
+    TRACE_EVENTS.lock().unwrap().extend(shift_time_trace(trace, offset, std::process::id(), lane));
+}
+
+// the events of a clang trace, moved to the spawn time and into a lane of our trace
+fn shift_time_trace(mut trace: Value, offset: u64, pid: u32, lane: usize) -> Vec<Value> {
+    let Some(events) = trace.get_mut("traceEvents").and_then(Value::as_array_mut) else {
+        return Vec::new();
+    };
+
+    let mut merged = Vec::new();
+    for mut event in events.drain(..) {
+        // metadata events of clang would rename our lanes
+        if event.get("ph").and_then(Value::as_str) == Some("M") {
+            continue;
+        }
+
+        if let Some(ts) = event.get("ts").and_then(Value::as_u64) {
+            event["ts"] = Value::from(ts + offset);
+        }
+        event["pid"] = Value::from(pid);
+        event["tid"] = Value::from(lane);
+
+        merged.push(event);
+    }
+
+    merged
+}
+
+pub fn save_trace(lanes: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let mut events = TRACE_EVENTS.lock().unwrap().clone();
+
+    for lane in 0..=lanes {
+        let name = if lane == 0 { "main".to_string() } else { format!("worker {}", lane) };
+        events.push(serde_json::json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": std::process::id(),
+            "tid": lane,
+            "args": { "name": name },
+        }));
+    }
+
+    let trace = serde_json::json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    });
+
+    std::fs::write(get_cxon_cache_dir().join(TRACE_FILE), serde_json::to_string(&trace)?)?;
+
+    Ok(())
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        record(&self.name, self.cat, self.start, Instant::now());
+    }
+}
+
+#[test]
+fn test_shift_time_trace() {
+    let trace = serde_json::json!({
+        "traceEvents": [
+            { "name": "Source", "ph": "X", "ts": 100, "dur": 50, "pid": 4242, "tid": 4242, "args": { "detail": "vector" } },
+            { "name": "Total Frontend", "ph": "X", "ts": 0, "dur": 900, "pid": 4242, "tid": 4243 },
+            { "name": "thread_name", "ph": "M", "pid": 4242, "tid": 4242, "args": { "name": "clang" } }
+        ],
+        "beginningOfTime": 1700000000000000u64
+    });
+
+    let events = shift_time_trace(trace, 2_000_000, 7, 3);
+
+    assert_eq!(events, [
+        serde_json::json!({ "name": "Source", "ph": "X", "ts": 2_000_100, "dur": 50, "pid": 7, "tid": 3, "args": { "detail": "vector" } }),
+        serde_json::json!({ "name": "Total Frontend", "ph": "X", "ts": 2_000_000, "dur": 900, "pid": 7, "tid": 3 }),
+    ]);
+    assert!(shift_time_trace(serde_json::json!({}), 0, 7, 3).is_empty());
+}
@@ -1,6 +1,6 @@
-use std::{env::current_dir, path::PathBuf, sync::{LazyLock, Mutex}};
+use std::{collections::HashMap, env::current_dir, path::PathBuf, sync::{LazyLock, Mutex}};
 
-use crate::utils;
+use crate::{toolchain::env::ENV_VARS, utils};
 
 static ARGS: LazyLock<Mutex<CliArgs>> = LazyLock::new(|| {
     Mutex::new(CliArgs::new())
@@ -10,28 +10,123 @@ pub fn get_args() -> CliArgs {
     ARGS.lock().unwrap().clone()
 }
 
+#[derive(Clone, PartialEq)]
+pub enum CliCommand {
+    Build,
+    // builds and runs the executable
+    Run,
+}
+
 #[derive(Clone)]
 pub struct CliArgs {
+    pub command: CliCommand,
     pub project_dir: PathBuf,
+    // --profile, overrides the profile field of cxon.json
+    pub profile: Option<String>,
+    // -l, don't start new jobs while the load average is above this value
+    pub max_load: Option<f64>,
+    // -v, prints the effective compilers and flags
+    pub verbose: bool,
+    // `CC=clang`, overrides the environment variables of the same name
+    pub env_overrides: HashMap<String, String>,
+    // the arguments after `--`, passed to the program by `cxon run`
+    pub run_args: Vec<String>,
 }
 
 impl CliArgs {
     pub fn new() -> Self {
-        let arg_col: Vec<String> = std::env::args().collect();
+        let mut command = None;
+        let mut project_dir = None;
+        let mut profile = None;
+        let mut max_load = None;
+        let mut verbose = false;
+        let mut env_overrides = HashMap::new();
+        let mut run_args = Vec::new();
 
-        if arg_col.len() <= 1 {
-            let mut project_dir = current_dir()
-                    .expect("Failed to get project directory automatically");
-            project_dir = utils::normalize_and_canonicalize_path(project_dir);
+        let mut arg_col = std::env::args().skip(1);
+        while let Some(arg) = arg_col.next() {
+            match arg.as_str() {
+                // `cxon [dir]` is the same as `cxon build [dir]`
+                "build" if command.is_none() && project_dir.is_none() => {
+                    command = Some(CliCommand::Build);
+                }
+                "run" if command.is_none() && project_dir.is_none() => {
+                    command = Some(CliCommand::Run);
+                }
+                "--" => {
+                    run_args.extend(arg_col.by_ref());
+                }
+                "--profile" => {
+                    let value = arg_col.next()
+                        .unwrap_or_else(|| panic!("Missing value for {}", arg));
+                    profile = Some(value);
+                }
+                _ if arg.starts_with("--profile=") => {
+                    profile = Some(arg["--profile=".len()..].to_string());
+                }
+                "-l" | "--max-load" => {
+                    let value = arg_col.next()
+                        .unwrap_or_else(|| panic!("Missing value for {}", arg));
+                    max_load = Some(Self::parse_load(&value));
+                }
+                // make style `-l4`
+                _ if arg.starts_with("-l") => {
+                    max_load = Some(Self::parse_load(&arg[2..]));
+                }
+                "-v" | "--verbose" => {
+                    verbose = true;
+                }
+                _ if arg.starts_with('-') => {
+                    panic!("Unknown option: {}", arg);
+                }
+                // make style `CC=clang`
+                _ if let Some((name, value)) = arg.split_once('=')
+                    && !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') => {
+                    if !ENV_VARS.contains(&name) {
+                        panic!("Unsupported variable: {}. Supported variables are: {}", name, ENV_VARS.join(", "));
+                    }
+                    env_overrides.insert(name.to_string(), value.to_string());
+                }
+                _ => {
+                    if project_dir.is_some() {
+                        panic!("Only one project directory can be specified, got another one: {}", arg);
+                    }
+                    project_dir = Some(PathBuf::from(arg));
+                }
+            }
+        }
 
-            return Self {
-                project_dir,
-            };
+        let command = command.unwrap_or(CliCommand::Build);
+        if !run_args.is_empty() && !matches!(command, CliCommand::Run) {
+            panic!("Arguments after -- are only passed to the program by `cxon run`, got: {}", run_args.join(" "));
         }
 
-        let project_dir = PathBuf::from(arg_col[1].clone());
+        Self {
+            command,
+            project_dir: Self::resolve_project_dir(project_dir),
+            profile,
+            max_load,
+            verbose,
+            env_overrides,
+            run_args,
+        }
+    }
+
+    fn parse_load(value: &str) -> f64 {
+        value.parse()
+            .unwrap_or_else(|_| panic!("Invalid load average: {}", value))
+    }
+
+    fn resolve_project_dir(project_dir: Option<PathBuf>) -> PathBuf {
+        let Some(project_dir) = project_dir else {
+            let project_dir = current_dir()
+                    .expect("Failed to get project directory automatically");
+            return utils::normalize_and_canonicalize_path(project_dir);
+        };
+
         if !project_dir.exists() {
-            Err(()).expect(format!("cxon project dir is not available: {}", project_dir.display()).as_str())
+            panic!("cxon project dir is not available: {}", project_dir.display());
         }
 
         // remove cxon.json if it's included in the path
@@ -41,11 +136,9 @@ impl CliArgs {
             project_dir
         };
 
-        let project_dir = 
+        let project_dir =
             if project_dir.is_absolute() { project_dir } else { project_dir.canonicalize().unwrap() };
 
-        Self {
-            project_dir: utils::normalize_and_canonicalize_path(project_dir),
-        }
+        utils::normalize_and_canonicalize_path(project_dir)
     }
-}
\ No newline at end of file
+}
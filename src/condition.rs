@@ -0,0 +1,151 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::toolchain::compiler_info::{self, CompilerInfo};
+
+// Extra settings merged into cxon.json when a condition matches
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConditionalConfig {
+    pub flags:    Option<Vec<String>>,
+    pub cflags:   Option<Vec<String>>,
+    pub cxxflags: Option<Vec<String>>,
+    pub ldflags:  Option<Vec<String>>,
+
+    pub sources: Option<Vec<PathBuf>>,
+
+    pub defines: Option<Vec<String>>,
+    pub include: Option<Vec<PathBuf>>,
+    pub link:    Option<Vec<PathBuf>>,
+    pub libs:    Option<Vec<String>>,
+}
+
+// What the conditions of cxon.json are evaluated against
+pub struct ConditionContext {
+    pub os: String,
+    pub family: String,
+    pub arch: String,
+    pub toolchain: String,
+    pub profile: String,
+    pub compiler: CompilerInfo,
+}
+
+impl ConditionContext {
+    pub fn new(toolchain: &str, profile: &str, compiler: &CompilerInfo) -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            family: std::env::consts::FAMILY.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            toolchain: toolchain.to_lowercase(),
+            profile: profile.to_string(),
+            compiler: compiler.clone(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Vec<&str> {
+        match key {
+            // `os=unix` matches every unix-like system
+            "os" => vec![&self.os, &self.family],
+            "arch" => vec![&self.arch],
+            "toolchain" => vec![&self.toolchain],
+            "profile" => vec![&self.profile],
+            "compiler" => vec![&self.compiler.vendor],
+            "compiler_version" => vec![&self.compiler.version],
+            "target" => self.compiler.target.iter().map(String::as_str).collect(),
+            _ => panic!("Unknown condition key: {}. Supported keys are: os, arch, toolchain, profile, compiler, compiler_version, target", key),
+        }
+    }
+
+    /// Evaluates a condition such as `os=linux,arch=x86_64|aarch64`. All the
+    /// comma separated terms have to match, `|` separates alternative values.
+    /// `compiler_version` is compared by version, as in `compiler_version>=11`.
+    pub fn matches(&self, condition: &str) -> bool {
+        condition
+            .split(',')
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .all(|term| {
+                let Some(index) = term.find(['=', '<', '>']) else {
+                    panic!("Invalid condition: {}. Conditions are written as key=value", condition);
+                };
+                let key = term[..index].trim();
+                let rest = &term[index..];
+                let operator = if rest.starts_with(">=") || rest.starts_with("<=") { &rest[..2] } else { &rest[..1] };
+                let values = &rest[operator.len()..];
+
+                if key == "compiler_version" {
+                    return values.split('|').any(|value| self.compare_version(operator, value.trim()));
+                }
+                if operator != "=" {
+                    panic!("Invalid condition: {}. Only compiler_version can be compared with {}", condition, operator);
+                }
+
+                let actual = self.get(key);
+                values
+                    .split('|')
+                    .map(|value| value.trim().to_lowercase())
+                    .any(|value| actual.contains(&value.as_str()))
+            })
+    }
+
+    // an unknown compiler version never matches
+    fn compare_version(&self, operator: &str, value: &str) -> bool {
+        if self.compiler.version.is_empty() {
+            return false;
+        }
+
+        let ordering = compiler_info::compare_versions(&self.compiler.version, value);
+        match operator {
+            "="  => ordering.is_eq(),
+            "<"  => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            ">"  => ordering.is_gt(),
+            ">=" => ordering.is_ge(),
+            _ => unreachable!(),
+        }
+    }
+
+    // sections of `platform` keyed by os and `when` keyed by conditions, in a stable order
+    pub fn select<'a>(
+        &self,
+        platform: &'a BTreeMap<String, ConditionalConfig>,
+        when: &'a BTreeMap<String, ConditionalConfig>,
+    ) -> Vec<&'a ConditionalConfig> {
+        let platform = platform
+            .iter()
+            .filter(|(os, _)| self.matches(&format!("os={}", os)));
+        let when = when
+            .iter()
+            .filter(|(condition, _)| self.matches(condition));
+
+        platform.chain(when).map(|(_, section)| section).collect()
+    }
+}
+
+#[test]
+fn test_condition_matches() {
+    let context = ConditionContext {
+        os: "linux".to_string(),
+        family: "unix".to_string(),
+        arch: "x86_64".to_string(),
+        toolchain: "gnu".to_string(),
+        profile: "release".to_string(),
+        compiler: CompilerInfo {
+            vendor: "gcc".to_string(),
+            version: "12.2.0".to_string(),
+            target: Some("x86_64-linux-gnu".to_string()),
+            default_cxx_std: Some("17".to_string()),
+        },
+    };
+
+    assert!(context.matches("os=linux"));
+    assert!(context.matches("os=unix"));
+    assert!(context.matches("os=windows|linux, toolchain=gnu"));
+    assert!(context.matches("arch=x86_64,profile=release"));
+    assert!(!context.matches("os=linux,toolchain=msvc"));
+    assert!(!context.matches("profile=debug"));
+    assert!(context.matches("compiler=gcc,compiler_version>=11"));
+    assert!(context.matches("compiler_version=12"));
+    assert!(!context.matches("compiler_version<12.1"));
+    assert!(context.matches("target=x86_64-linux-gnu"));
+}
@@ -1,10 +1,10 @@
 use core::panic;
-use std::{fs, path::{Path, PathBuf}, sync::{LazyLock, RwLock}};
+use std::{collections::{BTreeMap, HashMap}, fs, path::{Path, PathBuf}, sync::{LazyLock, RwLock}};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{cli::arg::{self, get_args}, toolchain::{TargetType, ToolChain, ToolChainTrait}};
-use crate::utils;
+use crate::{cli::arg::{self, get_args}, condition::{ConditionContext, ConditionalConfig}, extends, object::source, profile::{self, BuildProfile, Profile}, toolchain::{TargetType, Toolchain, compiler_info::CompilerInfo, wasm::WasmConfig, detect, env::{self, Origin}, settings::{DebugInfo, LanguageStandard, Optimization, Warnings}}};
+use crate::{utils, vars::Variables};
 
 static CONFIG: LazyLock<RwLock<CxonConfig>> = LazyLock::new(|| {
     RwLock::new({
@@ -30,14 +30,39 @@ pub struct CxonConfig {
     #[serde(default = "default_export_compile_commands")]
     pub export_compile_commands: bool,
     pub export_compile_commands_path: Option<PathBuf>,
+    #[serde(default)]
+    pub export_build_summary: bool,
+    pub export_build_summary_path: Option<PathBuf>,
+    #[serde(default = "default_build_summary_top")]
+    pub build_summary_top: usize,
 
     // toolchain settings
-    pub toolchain: String,
+    // a built-in toolchain such as gnu or zig, or a toolchain file, found in PATH when it's missing
+    pub toolchain: Option<String>,
+    #[serde(skip)]
+    resolved_toolchain: Option<Toolchain>,
+    #[serde(skip)]
+    compiler_info: CompilerInfo,
+
+    // cross compilation
+    pub target: Option<String>,      // such as aarch64-linux-gnu
+    pub sysroot: Option<PathBuf>,
+    pub tool_prefix: Option<String>, // derived from target for gnu
+
     pub cc:  Option<String>,
     pub cxx: Option<String>,
+    #[serde(alias = "ar")]
+    pub archiver: Option<String>,
+    // default, lld, mold, gold or bfd for -fuse-ld=, otherwise the program linking executables and shared libraries, cxx by default
+    pub linker: Option<String>,
+
+    // output and link settings of the wasm toolchain
+    pub wasm: Option<WasmConfig>,
 
     // building settings
     pub threads: Option<usize>,
+    max_load: Option<f64>,
+    pub memory_per_job: Option<u64>, // MiB
 
     // temp directory
     #[serde(default = "default_build_dir")]
@@ -45,22 +70,52 @@ pub struct CxonConfig {
     #[serde(default = "default_output_dir")]
     pub output_dir: PathBuf,
 
-    #[serde(default = "default_debug_flag")]
-    debug: bool,
+    #[serde(default)]
+    pub time_trace: bool,
+
+    // build profiles
+    profile: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+    #[serde(skip)]
+    build_profile: BuildProfile,
+
+    // deprecated, selects the debug or release profile if no profile is given
+    debug: Option<bool>,
+
+    // portable compiler settings, override the built-in profiles
+    c_std:        Option<LanguageStandard>,
+    cxx_std:      Option<LanguageStandard>,
+    optimization: Option<Optimization>,
+    warnings:     Option<Warnings>,
+    debug_info:   Option<DebugInfo>,
 
     // compiler flags
     flags:    Option<Vec<String>>,
     cflags:   Option<Vec<String>>,
     cxxflags: Option<Vec<String>>,
+    // linker flags, for executables and shared libraries
+    ldflags:  Option<Vec<String>>,
 
-    // source files
+    // source files, directories and glob patterns
     pub sources: Option<Vec<PathBuf>>,
+    exclude: Option<Vec<String>>,
 
     // compiler defines and includes
     defines: Option<Vec<String>>,
     include: Option<Vec<PathBuf>>,
     link:    Option<Vec<PathBuf>>,
     libs:    Option<Vec<String>>,
+
+    // settings merged in for an os (`platform`) or for conditions such as `os=linux,toolchain=gnu` (`when`)
+    #[serde(default)]
+    platform: BTreeMap<String, ConditionalConfig>,
+    #[serde(default)]
+    when: BTreeMap<String, ConditionalConfig>,
+
+    // user-defined variables, used as ${name} like the built-in ones
+    #[serde(default)]
+    vars: BTreeMap<String, String>,
 }
 
 impl CxonConfig {
@@ -71,16 +126,11 @@ impl CxonConfig {
             path.to_path_buf()
         };
 
-        let content = fs::read_to_string(&file_path).expect(
-            format!(
-                "Failed to read cxon.json file from {}",
-                file_path.to_string_lossy()
-            )
-            .as_str(),
-        );
-
-        let mut cxon: CxonConfig = serde_json::from_str(&content)
-            .expect("Failed to parse cxon configuration");
+        // cxon.json may contain comments, trailing commas and unquoted keys, and extend
+        // other files. Invalid fields are reported with the file defining them, so only
+        // the fields missing from all of them are left to fail here.
+        let mut cxon: CxonConfig = serde_json::from_value(extends::load_config_value(&file_path))
+            .unwrap_or_else(|err| panic!("Failed to parse cxon configuration {}: {}", file_path.display(), err));
 
         // Target name check
         if cxon.target_name.is_none() {
@@ -93,18 +143,154 @@ impl CxonConfig {
             panic!("Unsupported target type: {}. Supported target types are: executable, static_lib, shared_lib, object_lib", cxon.target_type);
         }
 
+        // Toolchain check
+        let toolchain = detect::resolve_toolchain(&cxon);
+
+        // Profile selection, --profile overrides the profile field
+        let profile_name = get_args().profile
+            .or(cxon.profile.clone())
+            .unwrap_or_else(|| match cxon.debug {
+                Some(false) => "release".to_string(),
+                _ => profile::DEFAULT_PROFILE.to_string(),
+            });
+        let base = Profile {
+            optimization: cxon.optimization,
+            debug_info: cxon.debug_info,
+            warnings: cxon.warnings,
+            ..Default::default()
+        };
+        cxon.build_profile = profile::resolve_profile(&profile_name, &cxon.profiles, &base);
+
+        cxon.resolved_toolchain = Some(toolchain);
+        cxon.expand_build_dir();
+
+        // identify the c++ compiler once, conditions and language standards depend on it
+        let compiler = cxon.get_cxx(cxon.get_toolchain()).0;
+        let cache_dir = cxon.get_profile_build_dir().join(".cxon");
+        cxon.compiler_info = CompilerInfo::load(&compiler, cxon.get_toolchain().family, &cache_dir);
+        if let Some(target) = &cxon.target {
+            // clang reports the host without --target
+            cxon.compiler_info.target = Some(target.clone());
+        }
+
+        cxon.merge_conditional_configs();
+        cxon.expand_variables();
+        cxon.check_language_standards();
+
         // Source file check
         if cxon.sources.is_none() || cxon.sources.as_ref().unwrap().is_empty() {
             panic!("No source files specified in cxon configuration");
         }
 
-        // Toolchain check
-        let supported_toolchains = ["gnu", "llvm", "msvc"];
-        if !supported_toolchains.contains(&cxon.toolchain.as_str()) {
-            panic!("Unsupported toolchain: {}. Supported toolchains are: {:?}", cxon.toolchain, supported_toolchains);
+        cxon.resolve_paths()
+    }
+
+    fn merge_conditional_configs(&mut self) {
+        let context = ConditionContext::new(&self.get_toolchain().name, &self.build_profile.name, &self.compiler_info);
+
+        let sections: Vec<ConditionalConfig> = context
+            .select(&self.platform, &self.when)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        fn merge<T>(target: &mut Option<Vec<T>>, extra: Option<Vec<T>>) {
+            if let Some(extra) = extra {
+                target.get_or_insert_with(Vec::new).extend(extra);
+            }
         }
 
-        cxon.resolve_paths()
+        for section in sections {
+            merge(&mut self.flags,    section.flags);
+            merge(&mut self.cflags,   section.cflags);
+            merge(&mut self.cxxflags, section.cxxflags);
+            merge(&mut self.ldflags,  section.ldflags);
+            merge(&mut self.sources,  section.sources);
+            merge(&mut self.defines,  section.defines);
+            merge(&mut self.include,  section.include);
+            merge(&mut self.link,     section.link);
+            merge(&mut self.libs,     section.libs);
+        }
+    }
+
+    fn builtin_variables(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("project_dir".to_string(), get_args().project_dir.to_string_lossy().to_string()),
+            ("profile".to_string(), self.build_profile.name.clone()),
+            ("toolchain".to_string(), self.get_toolchain().name.clone()),
+        ])
+    }
+
+    // build_dir can't refer to itself, so it is expanded before the other fields
+    fn expand_build_dir(&mut self) {
+        let vars = Variables::new(self.builtin_variables(), self.vars.clone());
+        self.build_dir = Self::expand_path(&vars, &self.build_dir);
+    }
+
+    // the build directory of the selected profile, before it's created
+    fn get_profile_build_dir(&self) -> PathBuf {
+        get_args().project_dir
+            .join(&self.build_dir)
+            .join(self.get_build_subdir())
+    }
+
+    // cross builds are kept apart from host builds
+    fn get_build_subdir(&self) -> PathBuf {
+        match &self.target {
+            Some(target) => Path::new(target).join(&self.build_profile.output_dir),
+            None => self.build_profile.output_dir.clone(),
+        }
+    }
+
+    // expands ${project_dir}, ${build_dir}, ${profile}, ${toolchain}, ${env:NAME} and the user-defined vars
+    fn expand_variables(&mut self) {
+        let mut builtin = self.builtin_variables();
+        builtin.insert("build_dir".to_string(), self.get_profile_build_dir().to_string_lossy().to_string());
+
+        let vars = Variables::new(builtin, self.vars.clone());
+
+        let expand_strings = |strings: &mut Option<Vec<String>>| {
+            strings.iter_mut().flatten().for_each(|s| *s = vars.expand(s));
+        };
+        let expand_paths = |paths: &mut Option<Vec<PathBuf>>| {
+            paths.iter_mut().flatten().for_each(|path| *path = Self::expand_path(&vars, path));
+        };
+
+        self.target_name = self.target_name.as_ref().map(|name| vars.expand(name));
+        self.output_dir = Self::expand_path(&vars, &self.output_dir);
+        self.sysroot = self.sysroot.as_ref().map(|path| get_args().project_dir.join(Self::expand_path(&vars, path)));
+        self.export_compile_commands_path = self.export_compile_commands_path.as_ref().map(|path| Self::expand_path(&vars, path));
+        self.export_build_summary_path = self.export_build_summary_path.as_ref().map(|path| Self::expand_path(&vars, path));
+
+        expand_strings(&mut self.flags);
+        expand_strings(&mut self.cflags);
+        expand_strings(&mut self.cxxflags);
+        expand_strings(&mut self.ldflags);
+        expand_strings(&mut self.defines);
+        expand_strings(&mut self.libs);
+        expand_strings(&mut self.exclude);
+        expand_paths(&mut self.sources);
+        expand_paths(&mut self.include);
+        expand_paths(&mut self.link);
+
+        self.build_profile.flags.iter_mut().for_each(|flag| *flag = vars.expand(flag));
+        self.build_profile.defines.iter_mut().for_each(|define| *define = vars.expand(define));
+    }
+
+    // refuses standards the compiler doesn't know, and uses draft names such as c++2a where needed
+    fn check_language_standards(&mut self) {
+        let info = &self.compiler_info;
+
+        if let Some(std) = &self.c_std {
+            self.c_std = Some(info.check_standard(std, false).unwrap_or_else(|err| panic!("{}", err)));
+        }
+        if let Some(std) = &self.cxx_std {
+            self.cxx_std = Some(info.check_standard(std, true).unwrap_or_else(|err| panic!("{}", err)));
+        }
+    }
+
+    fn expand_path(vars: &Variables, path: &Path) -> PathBuf {
+        PathBuf::from(vars.expand(&path.to_string_lossy()))
     }
 
     fn init_dir(path: PathBuf, cda: bool) -> PathBuf {
@@ -132,16 +318,27 @@ impl CxonConfig {
     fn resolve_paths(self) -> Self {
         let mut cxon = self;
 
-        // Create build and output directories if they don't exist
-        cxon.build_dir  = Self::init_dir(cxon.build_dir, true);
-        cxon.output_dir = Self::init_dir(cxon.output_dir, true);
+        // Create build and output directories of the profile if they don't exist
+        cxon.build_dir  = Self::init_dir(cxon.build_dir.join(cxon.get_build_subdir()), true);
+        cxon.output_dir = Self::init_dir(cxon.output_dir.join(cxon.get_build_subdir()), true);
 
         if let Some(export_path) = &cxon.export_compile_commands_path {
             cxon.export_compile_commands_path = Some(Self::init_dir(export_path.clone(), true));
         }
 
+        if let Some(summary_path) = &cxon.export_build_summary_path
+            && summary_path.is_relative() {
+            cxon.export_build_summary_path = Some(get_args().project_dir.join(summary_path));
+        }
+
         if let Some(sources) = cxon.sources {
-            cxon.sources = Some(Self::init_dirs(sources, false));
+            let exclude = cxon.exclude.clone().unwrap_or_default();
+            let sources = source::expand_sources(&sources, &exclude, &get_args().project_dir);
+            if sources.is_empty() {
+                panic!("No source files found for the sources in cxon configuration");
+            }
+
+            cxon.sources = Some(sources);
         }
         if let Some(includes) = cxon.include {
             cxon.include = Some(Self::init_dirs(includes, false));
@@ -167,17 +364,68 @@ impl CxonConfig {
         }
     }
 
-    pub fn get_toolchain(&self) -> ToolChain {
-        match self.toolchain.to_lowercase().as_str() {
-            "gnu"  => ToolChain::GNU(),
-            "llvm" => ToolChain::LLVM(),
-            "msvc" => ToolChain::MSVC(),
-            _ => panic!("Unsupported toolchain: {}. Supported toolchains are: gnu, llvm, msvc", self.toolchain),
+    // the toolchain of cxon.json, or the one matching the compilers or found in PATH
+    pub fn get_toolchain(&self) -> &Toolchain {
+        self.resolved_toolchain.as_ref().expect("The toolchain isn't resolved yet")
+    }
+
+    pub fn get_threads(&self) -> usize {
+        match self.threads {
+            Some(count) => count.max(1),
+            None => num_cpus::get().saturating_sub(1).max(1),
         }
     }
 
+    // the -l option overrides max_load of cxon.json
+    pub fn get_max_load(&self) -> Option<f64> {
+        get_args().max_load.or(self.max_load)
+    }
+
+    pub fn get_compiler_info(&self) -> &CompilerInfo {
+        &self.compiler_info
+    }
+
+    pub fn get_profile(&self) -> &BuildProfile {
+        &self.build_profile
+    }
+
     pub fn get_debug_flag(&self) -> bool {
-        self.debug
+        self.build_profile.debug_info != DebugInfo::None
+    }
+
+    // --target and --sysroot, for compiling and linking
+    pub fn get_cross_args(&self, toolchain: &Toolchain) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let (Some(target), Some(flag)) = (&self.target, &toolchain.flags.target) {
+            args.push(flag.replace("{target}", target));
+        }
+        if let (Some(sysroot), Some(flag)) = (&self.sysroot, &toolchain.flags.sysroot) {
+            args.push(flag.replace("{sysroot}", &sysroot.to_string_lossy()));
+        }
+
+        args
+    }
+
+    // cross compilation, optimization, warning, debug info and language standard flags
+    pub fn get_setting_args(&self, toolchain: &Toolchain, is_c_file: bool) -> Vec<String> {
+        let profile = &self.build_profile;
+
+        let mut args = [
+            self.get_cross_args(toolchain),
+            toolchain.optimization_flags(profile.optimization),
+            toolchain.warning_flags(profile.warnings),
+            toolchain.debug_info_flags(profile.debug_info),
+        ].concat();
+
+        if is_c_file && let Some(std) = &self.c_std {
+            args.push(toolchain.c_std_flag(std));
+        }
+        if !is_c_file && let Some(std) = &self.cxx_std {
+            args.push(toolchain.cxx_std_flag(std));
+        }
+
+        args
     }
 
     fn get_compiler_flags(&self) -> Vec<String> {
@@ -187,9 +435,39 @@ impl CxonConfig {
             flags.extend(f.clone());
         }
 
+        flags.extend(self.build_profile.flags.clone());
+
         flags
     }
 
+    // CC: command line, environment, cxon.json, then the toolchain default
+    pub fn get_cc(&self, toolchain: &Toolchain) -> (String, Origin) {
+        env::resolve_tool("CC", self.cc.as_deref(), &toolchain.cc)
+    }
+
+    pub fn get_cxx(&self, toolchain: &Toolchain) -> (String, Origin) {
+        env::resolve_tool("CXX", self.cxx.as_deref(), &toolchain.cxx)
+    }
+
+    // AR: command line, environment, archiver of cxon.json, then the toolchain default
+    pub fn get_archiver(&self, toolchain: &Toolchain) -> (String, Origin) {
+        env::resolve_tool("AR", self.archiver.as_deref(), &toolchain.static_lib.linker)
+    }
+
+    // the linker field naming a program, or CXX where the toolchain links with its c++ compiler
+    pub fn get_link_driver(&self, toolchain: &Toolchain, default: &str) -> String {
+        if let Some(linker) = self.get_linker_program() {
+            return linker.to_string();
+        }
+
+        if default == toolchain.cxx {
+            self.get_cxx(toolchain).0
+        } else {
+            default.to_string()
+        }
+    }
+
+    // CPPFLAGS and CFLAGS of the environment go last so that they can override cxon.json
     pub fn get_cflags(&self) -> Vec<String> {
         let mut flags = self.get_compiler_flags();
 
@@ -197,6 +475,9 @@ impl CxonConfig {
             flags.extend(f.clone());
         }
 
+        flags.extend(env::flags("CPPFLAGS"));
+        flags.extend(env::flags("CFLAGS"));
+
         flags
     }
 
@@ -207,56 +488,118 @@ impl CxonConfig {
             flags.extend(f.clone());
         }
 
+        flags.extend(env::flags("CPPFLAGS"));
+        flags.extend(env::flags("CXXFLAGS"));
+
+        flags
+    }
+
+    // the linker field when it isn't default or one of the linkers selected with -fuse-ld=
+    pub fn get_linker_program(&self) -> Option<&str> {
+        match self.linker.as_deref().map(str::trim) {
+            Some(linker) if !linker.is_empty() && linker != "default" && !LINKER_FLAVORS.contains(&linker) => Some(linker),
+            _ => None,
+        }
+    }
+
+    // the linker field when it's one of lld, mold, gold and bfd
+    pub fn get_linker_flavor(&self) -> Option<&str> {
+        self.linker.as_deref().map(str::trim).filter(|linker| LINKER_FLAVORS.contains(linker))
+    }
+
+    // -fuse-ld= for the lld, mold, gold and bfd linkers
+    pub fn get_fuse_ld_args(&self, toolchain: &Toolchain) -> Vec<String> {
+        let Some(flavor) = self.get_linker_flavor() else {
+            return Vec::new();
+        };
+
+        let Some(flag) = &toolchain.flags.fuse_ld else {
+            panic!("The {} toolchain can't select the {} linker, set linker to the program linking instead", toolchain.name, flavor);
+        };
+
+        vec![flag.replace("{linker}", flavor)]
+    }
+
+    // ldflags of cxon.json, then LDFLAGS of the environment so that it can override them
+    pub fn get_ldflags(&self) -> Vec<String> {
+        let mut flags = self.ldflags.clone().unwrap_or_default();
+        flags.extend(env::flags("LDFLAGS"));
+
         flags
     }
 
-    pub fn get_define_args<T: ToolChainTrait>(&self) -> Vec<String> {
+    // `-sNAME=value` of the wasm section
+    pub fn get_link_setting_args(&self, toolchain: &Toolchain) -> Vec<String> {
+        self.wasm
+            .as_ref()
+            .map(|wasm| wasm.setting_args(toolchain))
+            .unwrap_or_default()
+    }
+
+    // the wasm section may ask for a page instead of the js loader
+    pub fn get_executable_extension(&self, toolchain: &Toolchain) -> String {
+        match &self.wasm {
+            Some(wasm) if toolchain.flags.link_setting.is_some() => wasm.extension().to_string(),
+            _ => toolchain.executable.extension.clone(),
+        }
+    }
+
+    // the program starting the executable for `cxon run`, nothing for native executables
+    pub fn get_runner(&self, toolchain: &Toolchain) -> Vec<String> {
+        match self.wasm.as_ref().and_then(|wasm| wasm.runner()) {
+            Some(runner) if toolchain.flags.link_setting.is_some() => runner,
+            _ => toolchain.executable.runner.clone(),
+        }
+    }
+
+    pub fn get_define_args(&self, toolchain: &Toolchain) -> Vec<String> {
         let mut args = Vec::new();
-        let Some(defines) = &self.defines else {
-            return args;
-        };
+        let defines = self.defines
+            .iter()
+            .flatten()
+            .chain(self.build_profile.defines.iter());
 
         for define in defines {
-            args.push(format!("{}{}", T::DEFINE_FLAG_PREFIX, define));
+            args.push(toolchain.define_arg(define));
         }
 
         args
     }
 
-    pub fn get_include_dir_args<T: ToolChainTrait>(&self) -> Vec<String> {
+    pub fn get_include_dir_args(&self, toolchain: &Toolchain) -> Vec<String> {
         let mut args = Vec::new();
         let Some(include_dirs) = &self.include else {
             return args;
         };
 
         for include_dir in include_dirs {
-            args.push(format!("{}{}", T::INCLUDE_FLAG_PREFIX, include_dir.to_str().unwrap().to_string()));
+            args.push(toolchain.include_arg(include_dir));
         }
 
         args
     }
 
-    pub fn get_link_dir_args<T: ToolChainTrait>(&self) -> Vec<String> {
+    pub fn get_link_dir_args(&self, toolchain: &Toolchain) -> Vec<String> {
         let mut args = Vec::new();
         let Some(link_dirs) = &self.link else {
             return args;
         };
 
         for link_dir in link_dirs {
-            args.push(format!("{}{}", T::LINK_DIR_FLAG_PREFIX, link_dir.to_str().unwrap().to_string()));
+            args.push(toolchain.link_dir_arg(link_dir));
         }
 
         args
     }
 
-    pub fn get_lib_args<T: ToolChainTrait>(&self) -> Vec<String> {
+    pub fn get_lib_args(&self, toolchain: &Toolchain) -> Vec<String> {
         let mut args = Vec::new();
         let Some(libs) = &self.libs else {
             return args;
         };
 
         for lib in libs {
-            args.push(format!("{}{}", T::LINK_LIB_FLAG_PREFIX, lib));
+            args.push(toolchain.link_lib_arg(lib));
         }
 
         args
@@ -267,6 +610,9 @@ fn default_target_type() -> String {
     "executable".to_string()
 }
 
+// the linkers cxon.json may select by name, passed as -fuse-ld=
+const LINKER_FLAVORS: [&str; 4] = ["lld", "mold", "gold", "bfd"];
+
 fn default_build_dir() -> PathBuf {
     PathBuf::from("./build")
 }
@@ -279,8 +625,8 @@ fn default_export_compile_commands() -> bool {
     false
 }
 
-fn default_debug_flag() -> bool {
-    true
+fn default_build_summary_top() -> usize {
+    5
 }
 
 #[test]
@@ -288,3 +634,23 @@ fn test_cxon() {
     let config = CxonConfig::new("./cxon.json".as_ref());
     println!("Project: {:?}", config);
 }
+
+#[test]
+fn test_linker_field() {
+    let toolchain = crate::toolchain::ToolchainFamily::GNU.preset();
+    let config = |linker: &str| -> CxonConfig {
+        serde_json::from_value(serde_json::json!({ "project": "app", "linker": linker, "ldflags": ["-Wl,--as-needed"] })).unwrap()
+    };
+
+    let mold = config("mold");
+    assert_eq!(mold.get_fuse_ld_args(&toolchain), ["-fuse-ld=mold"]);
+    assert_eq!(mold.get_linker_program(), None);
+    assert_eq!(mold.ldflags.as_deref(), Some(["-Wl,--as-needed".to_string()].as_slice()));
+
+    let clang = config("clang++-18");
+    assert!(clang.get_fuse_ld_args(&toolchain).is_empty());
+    assert_eq!(clang.get_link_driver(&toolchain, &toolchain.executable.linker), "clang++-18");
+
+    let default = config("default");
+    assert_eq!((default.get_linker_program(), default.get_linker_flavor()), (None, None));
+}
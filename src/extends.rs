@@ -0,0 +1,237 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{cxon::CxonConfig, toolchain, utils::{self, jsonc}};
+
+// fields which are paths, resolved relative to the file declaring them
+const PATH_FIELDS: [&str; 5] = ["build_dir", "output_dir", "export_compile_commands_path", "export_build_summary_path", "sysroot"];
+const PATH_LIST_FIELDS: [&str; 3] = ["sources", "include", "link"];
+// sections which contain path lists themselves
+const CONDITIONAL_SECTIONS: [&str; 2] = ["platform", "when"];
+
+/// Loads a cxon.json and the files it `extends`, which may be a path or a
+/// list of paths. The extended files are merged in order and the file itself
+/// is merged last. Objects are merged recursively, other values are replaced,
+/// and lists are concatenated unless `"extends_lists": "replace"` is given.
+pub fn load_config_value(file_path: &Path) -> Value {
+    load(file_path, &mut Vec::new(), false)
+}
+
+fn load(file_path: &Path, chain: &mut Vec<PathBuf>, rebase: bool) -> Value {
+    let file_path = utils::normalize_and_canonicalize_path(
+        file_path.canonicalize().unwrap_or_else(|err| panic!("Failed to read {}: {}", file_path.display(), err)));
+
+    if chain.contains(&file_path) {
+        panic!("{} extends itself", file_path.display());
+    }
+
+    let content = std::fs::read_to_string(&file_path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {}", file_path.display(), err));
+    let mut value: Value = jsonc::from_str(&content)
+        .unwrap_or_else(|err| panic!("Failed to parse cxon configuration {}:{}:{}: {}",
+            file_path.display(), err.line, err.column, err.message));
+
+    if let Some(err) = field_type_error(&content, &value) {
+        panic!("Failed to parse cxon configuration {}:{}", file_path.display(), err);
+    }
+
+    let Some(object) = value.as_object_mut() else {
+        panic!("{} is not a JSON object", file_path.display());
+    };
+
+    let base_dir = file_path.parent().unwrap().to_path_buf();
+
+    // the project's own paths are resolved against the project directory later
+    if rebase {
+        rebase_paths(object, &base_dir);
+    }
+
+    let extends = match object.remove("extends") {
+        None => Vec::new(),
+        Some(Value::String(path)) => vec![path],
+        Some(Value::Array(paths)) => paths
+            .into_iter()
+            .map(|path| match path {
+                Value::String(path) => path,
+                _ => panic!("extends of {} must be a path or a list of paths", file_path.display()),
+            })
+            .collect(),
+        Some(_) => panic!("extends of {} must be a path or a list of paths", file_path.display()),
+    };
+
+    let replace_lists = match object.remove("extends_lists") {
+        None => false,
+        Some(Value::String(mode)) if mode == "append" => false,
+        Some(Value::String(mode)) if mode == "replace" => true,
+        Some(mode) => panic!("Unsupported extends_lists: {}. Supported values are: append, replace", mode),
+    };
+
+    if extends.is_empty() {
+        return value;
+    }
+
+    chain.push(file_path.clone());
+
+    let mut merged = Value::Object(Map::new());
+    for path in extends {
+        let base = load(&base_dir.join(path), chain, true);
+        merge(&mut merged, base, replace_lists);
+    }
+    merge(&mut merged, value, replace_lists);
+
+    chain.pop();
+
+    merged
+}
+
+fn merge(target: &mut Value, value: Value, replace_lists: bool) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(value)) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value, replace_lists),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(value)) if !replace_lists => {
+            target.extend(value);
+        }
+        (target, value) => *target = value,
+    }
+}
+
+fn rebase_paths(object: &mut Map<String, Value>, base_dir: &Path) {
+    for field in PATH_FIELDS {
+        if let Some(value) = object.get_mut(field) {
+            rebase_path(value, base_dir);
+        }
+    }
+
+    // toolchain files, built-in toolchains are names
+    if let Some(value) = object.get_mut("toolchain")
+        && value.as_str().is_some_and(toolchain::is_toolchain_file) {
+        rebase_path(value, base_dir);
+    }
+
+    for field in PATH_LIST_FIELDS {
+        if let Some(Value::Array(values)) = object.get_mut(field) {
+            values.iter_mut().for_each(|value| rebase_path(value, base_dir));
+        }
+    }
+
+    // glob patterns such as **/*_test.cpp apply to the project extending the file
+    if let Some(Value::Array(values)) = object.get_mut("exclude") {
+        values
+            .iter_mut()
+            .filter(|value| !value.as_str().is_some_and(|pattern| pattern.contains(['*', '?', '['])))
+            .for_each(|value| rebase_path(value, base_dir));
+    }
+
+    for section in CONDITIONAL_SECTIONS {
+        if let Some(Value::Object(sections)) = object.get_mut(section) {
+            for (_, section) in sections.iter_mut() {
+                if let Value::Object(section) = section {
+                    rebase_paths(section, base_dir);
+                }
+            }
+        }
+    }
+}
+
+// "line:column: message" of a field of the wrong type. A file may leave out
+// required fields which the files it extends or extending it give.
+fn field_type_error(content: &str, value: &Value) -> Option<String> {
+    let err = CxonConfig::deserialize(value).err()?;
+    if err.to_string().starts_with("missing field") {
+        return None;
+    }
+
+    // the text is only parsed again to find where the field is
+    match jsonc::from_str::<CxonConfig>(content) {
+        Err(err) => Some(format!("{}:{}: {}", err.line, err.column, err.message)),
+        Ok(_) => Some(format!(" {}", err)),
+    }
+}
+
+// paths starting with a variable such as ${env:SDK} are left as they are
+fn rebase_path(value: &mut Value, base_dir: &Path) {
+    if let Value::String(path) = value
+        && Path::new(path).is_relative()
+        && !path.starts_with("${") {
+        *path = base_dir.join(&path).to_string_lossy().to_string();
+    }
+}
+
+#[test]
+fn test_extends() {
+    let dir = std::env::temp_dir().join(format!("cxon_extends_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("common")).unwrap();
+    std::fs::create_dir_all(dir.join("app")).unwrap();
+
+    std::fs::write(dir.join("common/cxon.base.json"), r#"{
+        // shared settings
+        "toolchain": "gnu",
+        "flags": ["-Wall"],
+        "include": ["include"],
+        "profiles": { "release": { "flags": ["-flto"] } }
+    }"#).unwrap();
+    std::fs::write(dir.join("app/cxon.json"), r#"{
+        "extends": "../common/cxon.base.json",
+        "project": "app",
+        "flags": ["-Wextra"],
+        "profiles": { "release": { "defines": ["APP"] } }
+    }"#).unwrap();
+
+    let value = load_config_value(&dir.join("app/cxon.json"));
+    let common = dir.canonicalize().unwrap().join("common");
+
+    assert_eq!(value["toolchain"], "gnu");
+    assert_eq!(value["flags"], serde_json::json!(["-Wall", "-Wextra"]));
+    assert_eq!(value["include"], serde_json::json!([common.join("include").to_string_lossy()]));
+    assert_eq!(value["profiles"]["release"], serde_json::json!({ "flags": ["-flto"], "defines": ["APP"] }));
+    assert!(value.get("extends").is_none());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_extends_exclude() {
+    let dir = std::env::temp_dir().join(format!("cxon_extends_exclude_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("common/legacy")).unwrap();
+    std::fs::create_dir_all(dir.join("app/src")).unwrap();
+    for file in ["app/src/main.cpp", "app/src/main_test.cpp", "common/legacy/old.cpp"] {
+        std::fs::write(dir.join(file), "").unwrap();
+    }
+
+    std::fs::write(dir.join("common/cxon.base.json"), r#"{ "exclude": ["**/*_test.cpp", "legacy"] }"#).unwrap();
+    std::fs::write(dir.join("app/cxon.json"), r#"{ "extends": "../common/cxon.base.json", "project": "app" }"#).unwrap();
+
+    let value = load_config_value(&dir.join("app/cxon.json"));
+    let dir = dir.canonicalize().unwrap();
+
+    assert_eq!(value["exclude"], serde_json::json!(["**/*_test.cpp", dir.join("common/legacy").to_string_lossy()]));
+
+    let exclude: Vec<String> = serde_json::from_value(value["exclude"].clone()).unwrap();
+    let sources = crate::object::source::expand_sources(
+        &[PathBuf::from("src"), dir.join("common/legacy")], &exclude, &dir.join("app"));
+    assert_eq!(sources, [dir.join("app/src/main.cpp")]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_field_type_error() {
+    let content = "{\n    // shared settings\n    \"flags\": \"-Wall\"\n}";
+    let value: Value = jsonc::from_str(content).unwrap();
+    assert_eq!(field_type_error(content, &value).as_deref(),
+        Some("3:20: invalid type: string \"-Wall\", expected a sequence"));
+
+    // the project is given by the file extending this one
+    let content = r#"{ "flags": ["-Wall"] }"#;
+    assert_eq!(field_type_error(content, &jsonc::from_str(content).unwrap()), None);
+}
@@ -1,7 +1,16 @@
-use std::{collections::VecDeque, sync::{Arc, Mutex}, thread};
+use std::{collections::VecDeque, path::PathBuf, process::Command, sync::{Arc, Mutex}, thread, time::Instant};
 
-use crate::{compile_commands_json::generate_compile_commands_json, cxon::get_cxon_config, object::{output::ObjectCollection, source::Source}, toolchain::{ToolChain, ToolChainTrait, compiler, gnu::GNU, linker, llvm::LLVM, msvc::MSVC}};
+use crate::{build::{compiler_cache::CompilerCache, history, jobserver::get_jobserver, limits, progress, summary::BuildSummary, trace}, cli::arg::{CliCommand, get_args}, compile_commands_json::generate_compile_commands_json, cxon::get_cxon_config, object::{output::ObjectCollection, source::Source}, toolchain::{TargetType, compiler, env, get_toolchain, linker}};
 
+pub mod build {
+    pub mod compiler_cache;
+    pub mod history;
+    pub mod jobserver;
+    pub mod limits;
+    pub mod progress;
+    pub mod summary;
+    pub mod trace;
+}
 pub mod cli {
     pub mod arg;
 }
@@ -11,32 +20,72 @@ pub mod object {
 }
 pub mod toolchain;
 pub mod utils;
+pub mod vars;
+pub mod condition;
 pub mod cxon;
+pub mod extends;
+pub mod profile;
 pub mod compile_commands_json;
 
 fn main() -> () {
-    let toolchain = get_cxon_config()
-        .read()
-        .unwrap()
-        .get_toolchain();
-    
-    match toolchain {
-        ToolChain::GNU()  => build_project::<GNU>(),
-        ToolChain::LLVM() => build_project::<LLVM>(),
-        ToolChain::MSVC() => build_project::<MSVC>(),
+    trace::init();
+
+    // take over the jobserver pipe of a parent make before opening any file
+    let jobserver = get_jobserver();
+    if jobserver.is_inherited() {
+        println!("Sharing job slots with the parent jobserver");
+    }
+
+    match get_args().command {
+        CliCommand::Build => {
+            build_project();
+        }
+        CliCommand::Run => run_project(),
+    }
+}
+
+// builds the project and runs the executable with the arguments after `--`
+fn run_project() {
+    if !matches!(get_cxon_config().read().unwrap().get_target_type(), TargetType::Executable) {
+        panic!("Only executables can be run, {} is a library", get_cxon_config().read().unwrap().project);
     }
+
+    let output = build_project();
+    let runner = get_cxon_config().read().unwrap().get_runner(get_toolchain());
+
+    let mut command_line = runner;
+    command_line.push(output.to_string_lossy().to_string());
+    command_line.extend(get_args().run_args);
+
+    println!("Running {}", command_line.join(" "));
+    let status = Command::new(&command_line[0])
+        .args(&command_line[1..])
+        .status()
+        .unwrap_or_else(|err| panic!("Failed to run {}: {}", command_line[0], err));
+
+    std::process::exit(status.code().unwrap_or(1));
 }
 
-fn build_project<T: ToolChainTrait>() {
+// returns the path of the linked artifact
+fn build_project() -> PathBuf {
     let cxon = cxon::get_cxon_config();
+    let toolchain = get_toolchain();
+    println!("Building {} with the {} profile", cxon.read().unwrap().project, cxon.read().unwrap().get_profile().name);
+    if get_args().verbose {
+        env::print_effective(&cxon.read().unwrap(), toolchain);
+    }
 
-    let sources = cxon
+    let mut sources = cxon
         .read()
         .unwrap()
         .sources
         .clone()
         .expect("No source files specified in cxon configuration");
 
+    // start the slowest translation units first
+    history::sort_by_compile_time(&mut sources);
+
+    progress::set_total(sources.len());
     let sources = Arc::new(Mutex::new(VecDeque::from(sources)));
 
     let objects = ObjectCollection{
@@ -44,21 +93,43 @@ fn build_project<T: ToolChainTrait>() {
     };
     let objects = Arc::new(Mutex::new(objects));
 
-    let thread_count = match cxon.read().unwrap().threads {
-        Some(count) => count,
-        None => num_cpus::get() as usize - 1,
+    let thread_count = cxon.read().unwrap().get_threads();
+
+    if cxon.read().unwrap().time_trace && toolchain.flags.time_trace.is_none() {
+        eprintln!("Warning: time_trace is not supported by the {} toolchain", toolchain.name);
+    }
+
+    // hits of ccache or sccache are counted before and after compiling
+    let compiler_cache = {
+        let cxon = cxon.read().unwrap();
+        CompilerCache::detect(&cxon.get_cxx(toolchain).0).or_else(|| CompilerCache::detect(&cxon.get_cc(toolchain).0))
     };
+    let cache_hits_before = compiler_cache.and_then(|cache| cache.hit_count());
 
     let mut compile_threads = Vec::new();
 
-    for _ in 0..thread_count {
+    trace::record("setup", "setup", trace::start_time(), Instant::now());
+    let compile_start = Instant::now();
+
+    for index in 0..thread_count {
         let sources = sources.clone();
         let objects = objects.clone();
 
         compile_threads.push(thread::spawn(move || {
-            while let Some(source) = sources.lock().unwrap().pop_back() {
+            trace::set_lane(index + 1);
+
+            loop {
+                // the queue is unlocked before waiting for a token, so that the other threads can compile meanwhile
+                let next = sources.lock().unwrap().pop_back();
+                let Some(source) = next else {
+                    break;
+                };
+
+                // a make job slot isn't held while waiting for the load or memory to go down
+                let _slot = limits::wait_for_resources();
+                let _token = get_jobserver().acquire();
                 let source = Source::new(source.clone().as_path());
-                let obj = compiler::compile::<T>(source);
+                let obj = compiler::compile(source);
                 objects.lock().unwrap().objects.push(obj);
             }
         }));
@@ -67,14 +138,35 @@ fn build_project<T: ToolChainTrait>() {
     for thread in compile_threads {
         thread.join().unwrap();
     }
+    progress::done();
+    let compile_wall_time = compile_start.elapsed();
 
-    linker::link::<T>(objects.lock().unwrap().clone(), 
+    let cached = match (compiler_cache, cache_hits_before) {
+        (Some(cache), Some(before)) => cache.hit_count().map(|after| (cache, after.saturating_sub(before))),
+        _ => None,
+    };
+
+    let link_span = trace::span("link", "link");
+    let output = linker::link(objects.lock().unwrap().clone(), 
         get_cxon_config()
         .read()
         .unwrap()
         .get_target_type()
     );
+    drop(link_span);
+
+    trace::save_trace(thread_count).expect("Failed to save the build trace");
+    history::save_compile_times().expect("Failed to save compile times");
+
     if cxon.read().unwrap().export_compile_commands {
         generate_compile_commands_json().expect("Failed to export compile_commands.json")
     }
+
+    let summary = BuildSummary::new(output.clone(), thread_count, compile_wall_time, trace::start_time().elapsed(), cached);
+    summary.print();
+    if cxon.read().unwrap().export_build_summary {
+        summary.export().expect("Failed to export the build summary");
+    }
+
+    output
 }
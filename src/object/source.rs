@@ -60,4 +60,119 @@ impl Source {
     pub fn get_path(&self) -> &Path {
         &self.src_dir
     }
-}
\ No newline at end of file
+}
+
+// extensions of the translation units picked up from a directory entry
+const TRANSLATION_UNIT_EXTENSIONS: [&str; 4] = ["c", "cc", "cpp", "cxx"];
+
+/// Expands the `sources` of cxon.json. An entry may be a file, a directory
+/// whose C/C++ files are collected recursively, or a glob pattern such as
+/// `src/**/*.cpp`. Every expansion is sorted, duplicates are removed and the
+/// files matching an `exclude` pattern or directory are dropped.
+pub fn expand_sources(entries: &[PathBuf], exclude: &[String], base_dir: &Path) -> Vec<PathBuf> {
+    let exclude: Vec<(PathBuf, glob::Pattern)> = exclude
+        .iter()
+        .map(|pattern| {
+            let path = normalize(&base_dir.join(pattern));
+            let glob_pattern = glob::Pattern::new(path.to_str().unwrap())
+                .unwrap_or_else(|err| panic!("Invalid exclude pattern {}: {}", pattern, err));
+            (path, glob_pattern)
+        })
+        .collect();
+
+    let match_options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let is_excluded = |path: &Path| {
+        exclude.iter().any(|(dir, pattern)| path.starts_with(dir) || pattern.matches_path_with(path, match_options))
+    };
+
+    let mut sources: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+        let path = normalize(&base_dir.join(entry));
+        let path_str = path.to_str().unwrap();
+
+        let mut expanded = if path_str.contains(['*', '?', '[']) {
+            let matches: Vec<PathBuf> = glob::glob_with(path_str, match_options)
+                .unwrap_or_else(|err| panic!("Invalid source pattern {}: {}", entry.display(), err))
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .collect();
+
+            if matches.is_empty() {
+                eprintln!("Warning: source pattern {} matches no file", entry.display());
+            }
+
+            matches
+        } else if path.is_dir() {
+            collect_translation_units(&path)
+        } else if path.exists() {
+            vec![path]
+        } else {
+            panic!("Source file {} does not exist", path.display());
+        };
+
+        expanded.sort();
+        for source in expanded {
+            if !is_excluded(&source) && !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+    }
+
+    sources
+}
+
+fn collect_translation_units(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    let entries = std::fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Failed to read source directory {}: {}", dir.display(), err));
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        // skip hidden directories such as .git or the .cxon cache
+        if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+            continue;
+        }
+
+        if path.is_dir() {
+            files.extend(collect_translation_units(&path));
+        } else if path.extension().is_some_and(|ext| TRANSLATION_UNIT_EXTENSIONS.contains(&ext.to_str().unwrap_or(""))) {
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+// removes the `.` components, so that `./src/a.cpp` and `src/a.cpp` are the same source
+fn normalize(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+#[test]
+fn test_expand_sources() {
+    let dir = std::env::temp_dir().join(format!("cxon_expand_sources_{}", std::process::id()));
+    for file in ["main.cpp", "src/a.cpp", "src/b.c", "src/b.h", "src/gen/c.cc", "third_party/d.cpp"] {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+
+    let sources = expand_sources(
+        &[PathBuf::from("./main.cpp"), PathBuf::from("src"), PathBuf::from("**/*.cpp")],
+        &["src/gen".to_string(), "third_party/**".to_string()],
+        &dir,
+    );
+
+    let expected: Vec<PathBuf> = ["main.cpp", "src/a.cpp", "src/b.c"]
+        .iter()
+        .map(|file| dir.join(file))
+        .collect();
+    assert_eq!(sources, expected);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,146 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::toolchain::settings::{DebugInfo, Optimization, Warnings};
+
+pub const DEFAULT_PROFILE: &str = "debug";
+
+// A profile as written in cxon.json, every field falls back to the inherited profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub inherits: Option<String>,
+
+    pub optimization: Option<Optimization>,
+    #[serde(alias = "debug")]
+    pub debug_info: Option<DebugInfo>,
+    pub warnings: Option<Warnings>,
+
+    pub flags:   Option<Vec<String>>,
+    pub defines: Option<Vec<String>>,
+
+    // subdirectory of build_dir and output_dir, the default value is the profile name
+    pub output_dir: Option<PathBuf>,
+}
+
+// The profile selected for this build with all fields resolved
+#[derive(Debug, Clone, Default)]
+pub struct BuildProfile {
+    pub name: String,
+    pub optimization: Optimization,
+    pub debug_info: DebugInfo,
+    pub warnings: Warnings,
+    pub flags: Vec<String>,
+    pub defines: Vec<String>,
+    pub output_dir: PathBuf,
+}
+
+fn builtin_profile(name: &str) -> Option<Profile> {
+    let (optimization, debug_info, defines) = match name {
+        "debug"          => (Optimization::None,  DebugInfo::Full, vec![]),
+        "release"        => (Optimization::Speed, DebugInfo::None, vec!["NDEBUG".to_string()]),
+        "relwithdebinfo" => (Optimization::Speed, DebugInfo::Full, vec!["NDEBUG".to_string()]),
+        "minsizerel"     => (Optimization::Size,  DebugInfo::None, vec!["NDEBUG".to_string()]),
+        _ => return None,
+    };
+
+    Some(Profile {
+        inherits: None,
+        optimization: Some(optimization),
+        debug_info: Some(debug_info),
+        warnings: None,
+        flags: None,
+        defines: Some(defines),
+        output_dir: None,
+    })
+}
+
+/// Resolves a built-in or user-defined profile. The settings of the `base`
+/// profile (the top-level fields of cxon.json) override the built-in
+/// profiles, and are overridden by the user-defined ones.
+pub fn resolve_profile(name: &str, profiles: &HashMap<String, Profile>, base: &Profile) -> BuildProfile {
+    // from the selected profile down to the one it finally inherits from
+    let mut user_layers = Vec::new();
+    let mut builtin_layers = Vec::new();
+    let mut visited = Vec::new();
+    let mut next = Some(name.to_string());
+
+    while let Some(current) = next {
+        if visited.contains(&current) {
+            panic!("Profile {} inherits from itself", current);
+        }
+
+        let user = profiles.get(&current).cloned();
+        let builtin = builtin_profile(&current);
+        if user.is_none() && builtin.is_none() {
+            panic!("Unknown profile: {}. Built-in profiles are: debug, release, relwithdebinfo, minsizerel", current);
+        }
+
+        next = user.as_ref().and_then(|profile| profile.inherits.clone());
+        user_layers.extend(user);
+        builtin_layers.extend(builtin);
+        visited.push(current);
+    }
+
+    let mut profile = BuildProfile {
+        name: name.to_string(),
+        output_dir: PathBuf::from(name),
+        ..Default::default()
+    };
+
+    let layers = builtin_layers
+        .into_iter()
+        .rev()
+        .chain([base.clone()])
+        .chain(user_layers.into_iter().rev());
+
+    for layer in layers {
+        if let Some(optimization) = layer.optimization {
+            profile.optimization = optimization;
+        }
+        if let Some(debug_info) = layer.debug_info {
+            profile.debug_info = debug_info;
+        }
+        if let Some(warnings) = layer.warnings {
+            profile.warnings = warnings;
+        }
+        if let Some(flags) = layer.flags {
+            profile.flags = flags;
+        }
+        if let Some(defines) = layer.defines {
+            profile.defines = defines;
+        }
+        if let Some(output_dir) = layer.output_dir {
+            profile.output_dir = output_dir;
+        }
+    }
+
+    profile
+}
+
+#[test]
+fn test_resolve_profile() {
+    let profiles: HashMap<String, Profile> = serde_json::from_str(r#"{
+        "release":   { "flags": ["-flto"] },
+        "profiling": { "inherits": "release", "debug": true, "output_dir": "prof" }
+    }"#).unwrap();
+
+    let base = Profile {
+        warnings: Some(Warnings::Error),
+        optimization: Some(Optimization::Max),
+        ..Default::default()
+    };
+
+    let release = resolve_profile("release", &profiles, &base);
+    assert_eq!(release.optimization, Optimization::Max);
+    assert_eq!(release.warnings, Warnings::Error);
+    assert_eq!(release.flags, vec!["-flto"]);
+    assert_eq!(release.defines, vec!["NDEBUG"]);
+    assert_eq!(release.output_dir, PathBuf::from("release"));
+
+    let profiling = resolve_profile("profiling", &profiles, &Profile::default());
+    assert_eq!(profiling.optimization, Optimization::Speed);
+    assert_eq!(profiling.debug_info, DebugInfo::Full);
+    assert_eq!(profiling.flags, vec!["-flto"]);
+    assert_eq!(profiling.output_dir, PathBuf::from("prof"));
+}
@@ -1,9 +1,10 @@
-use std::{cmp, path::PathBuf, time::SystemTime};
+use std::{cmp, path::PathBuf, process::Stdio, time::{Instant, SystemTime}};
 
-use crate::{compile_commands_json::{CompileCommand, add_compile_command}, 
+use crate::{build::{history, jobserver::get_jobserver, progress, summary, trace},
+    compile_commands_json::{CompileCommand, add_compile_command}, 
     cxon::get_cxon_config,
     object::{output::{self, Object}, source::Source},
-    toolchain::ToolChainTrait,
+    toolchain::{Toolchain, env, get_toolchain, response_file::{self, Quoting}},
     utils::{self, get_object_target_path}
 };
 
@@ -16,17 +17,22 @@ pub struct CompilerPair {
 struct CompileFuncArgs {
     pub src_path: PathBuf,
     pub obj_path: PathBuf,
-    pub compiler: String,
+    // the compiler and its launcher, such as `ccache gcc`
+    pub compiler: Vec<String>,
     pub flags:    Vec<String>,
     pub defines:  Vec<String>,
     pub includes: Vec<String>,
+    pub time_trace: bool,
 }
 
-pub fn compile<T: ToolChainTrait>(src: Source) -> Object {
+pub fn compile(src: Source) -> Object {
     let cxon = get_cxon_config().read().unwrap(); 
-    let obj_path = get_object_target_path::<T>(&src).expect("Failed to get the target path of object file");
+    let toolchain = get_toolchain();
+    let obj_path = get_object_target_path(&src, toolchain).expect("Failed to get the target path of object file");
 
     if !need_recompile(&src, &obj_path) {
+        progress::up_to_date(src.get_path());
+        summary::record_up_to_date();
         return Object { 
             path: obj_path.clone(),
             modified: Some(obj_path
@@ -39,41 +45,55 @@ pub fn compile<T: ToolChainTrait>(src: Source) -> Object {
     }
 
     let is_c_file = src.get_path().extension().unwrap() == "c";
+    let (compiler, _) = if is_c_file { cxon.get_cc(toolchain) } else { cxon.get_cxx(toolchain) };
 
-    // get compiler flags
-    let mut flags = if is_c_file {
-        cxon.get_cflags()
+    // get compiler flags, portable settings go first so that raw flags can override them
+    let mut flags = cxon.get_setting_args(toolchain, is_c_file);
+    if is_c_file {
+        flags.extend(cxon.get_cflags());
     } else {
-        cxon.get_cxxflags()
-    };
+        flags.extend(cxon.get_cxxflags());
+    }
 
-    
-    // get debug flag
-    if cxon.get_debug_flag() {
-        flags.push(T::DEBUG_FLAG.to_string());
+    if cxon.time_trace && let Some(flag) = &toolchain.flags.time_trace {
+        flags.push(flag.clone());
     }
 
-    compile_handler::<T>(CompileFuncArgs {
+    compile_handler(toolchain, CompileFuncArgs {
         src_path: src.get_path().to_path_buf(),
         obj_path: obj_path.clone(),
-        compiler: if is_c_file { T::CC.to_string() } else { T::CXX.to_string() },
+        compiler: env::split_flags(&compiler),
         flags: flags,
-        defines: cxon.get_define_args::<T>(),
-        includes: cxon.get_include_dir_args::<T>(),
+        defines: cxon.get_define_args(toolchain),
+        includes: cxon.get_include_dir_args(toolchain),
+        time_trace: cxon.time_trace && toolchain.flags.time_trace.is_some(),
     })
 }
 
-fn compile_handler<T: ToolChainTrait>(args: CompileFuncArgs) -> Object {
-    let mut cmd = std::process::Command::new(args.compiler);
+fn compile_handler(toolchain: &Toolchain, args: CompileFuncArgs) -> Object {
+    let compile_args = toolchain.compile_args(&args.src_path, &args.obj_path);
+    let mut includes_and_defines = [args.includes, args.defines].concat();
+
+    // the include directories and defines of a large project may not fit on a command line
+    if response_file::is_too_long(&[args.compiler.as_slice(), &compile_args, &includes_and_defines, &args.flags].concat()) {
+        // named after the source, as a.c and a.cpp share the stem of their objects
+        let rsp_path = args.obj_path.with_file_name(args.src_path.file_name().unwrap()).with_added_extension("rsp");
+        includes_and_defines = vec![response_file::write(&rsp_path, &includes_and_defines, Quoting::of(toolchain.family))];
+    }
+
+    let mut cmd = std::process::Command::new(&args.compiler[0]);
     let cmd = cmd
-        .arg(T::ONLY_COMPILE_FLAG)
-        .arg(args.src_path.to_str().unwrap())
-        .arg(T::EXECUTABLE_OUTPUT_FLAG)
-        .arg(args.obj_path.to_str().unwrap())
-        .args(args.includes)
-        .args(args.defines)
-        .args(args.flags);
+        .args(&args.compiler[1..])
+        .args(compile_args)
+        .args(includes_and_defines)
+        .args(args.flags)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    get_jobserver().configure(cmd);
+
+    progress::start(&args.src_path);
 
+    let start = Instant::now();
     let status = cmd.spawn()
         .expect(format!("Failed to compile {}", args.src_path.to_str().unwrap()).as_str());
 
@@ -87,8 +107,16 @@ fn compile_handler<T: ToolChainTrait>(args: CompileFuncArgs) -> Object {
         .expect(format!("Failed to wait for the compilation process of {}", args.src_path.to_str().unwrap()).as_str());
 
     if output.status.success() {
-        println!("Compiled {} to {}", args.src_path.to_str().unwrap(), args.obj_path.to_str().unwrap());
+        history::record_compile_time(&args.src_path, start.elapsed());
+        summary::record_compiled(&args.src_path, start.elapsed());
+        trace::record(&progress::display_path(&args.src_path), "compile", start, Instant::now());
+        if args.time_trace {
+            // clang writes the trace next to the object file
+            trace::merge_time_trace(&args.obj_path.with_extension("json"), start);
+        }
+        progress::finish(&args.src_path, &output.stdout, &output.stderr);
     } else {
+        progress::fail(&args.src_path, &output.stdout, &output.stderr);
         panic!("Failed to compile {}", args.src_path.to_str().unwrap());
     }
 
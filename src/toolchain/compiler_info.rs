@@ -0,0 +1,277 @@
+use std::{path::Path, process::{Command, Stdio}, time::UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::toolchain::{ToolchainFamily, detect, env, settings::LanguageStandard};
+
+const CACHE_FILE: &str = "compiler_info.json";
+
+/// What the compiler of the build says about itself, cached in the build
+/// directory until the compiler binary changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompilerInfo {
+    // gcc, clang, apple-clang, msvc, or unknown
+    pub vendor: String,
+    pub version: String,
+    // such as x86_64-linux-gnu, the architecture only for msvc
+    pub target: Option<String>,
+    // such as 17, without a cxx_std field
+    pub default_cxx_std: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    compiler: String,
+    modified: u64,
+    info: CompilerInfo,
+}
+
+impl CompilerInfo {
+    pub fn load(compiler: &str, family: ToolchainFamily, cache_dir: &Path) -> CompilerInfo {
+        let modified = compiler_modified(compiler);
+        let cache_path = cache_dir.join(CACHE_FILE);
+
+        if let Ok(content) = std::fs::read_to_string(&cache_path)
+            && let Ok(entry) = serde_json::from_str::<CacheEntry>(&content)
+            && entry.compiler == compiler
+            && entry.modified == modified {
+            return entry.info;
+        }
+
+        let info = Self::identify(compiler, family);
+
+        let entry = CacheEntry { compiler: compiler.to_string(), modified, info: info.clone() };
+        if std::fs::create_dir_all(cache_dir).is_ok() {
+            // a missing cache only costs another identification
+            let _ = std::fs::write(&cache_path, serde_json::to_string_pretty(&entry).unwrap());
+        }
+
+        info
+    }
+
+    /// Runs the compiler to find its vendor, version, target and default
+    /// standard. cl only prints a banner, the others list their predefined
+    /// macros.
+    pub fn identify(compiler: &str, family: ToolchainFamily) -> CompilerInfo {
+        let words = env::split_flags(compiler);
+
+        match family {
+            ToolchainFamily::MSVC if detect::is_clang_cl(compiler) => {
+                let version = run(&words, &["--version"]).and_then(|output| parse_clang_version(&output));
+                CompilerInfo {
+                    vendor: "clang".to_string(),
+                    version: version.unwrap_or_default(),
+                    target: None,
+                    default_cxx_std: Some("14".to_string()),
+                }
+            }
+            ToolchainFamily::MSVC => run(&words, &[])
+                .map(|banner| parse_msvc_banner(&banner))
+                .unwrap_or_else(Self::unknown),
+            ToolchainFamily::GNU | ToolchainFamily::LLVM => {
+                let Some(macros) = run(&words, &["-x", "c++", "-E", "-dM", "-"]) else {
+                    return Self::unknown();
+                };
+
+                let mut info = parse_macros(&macros);
+                info.target = run(&words, &["-dumpmachine"])
+                    .map(|output| output.trim().to_string())
+                    .filter(|target| !target.is_empty());
+                info
+            }
+        }
+    }
+
+    fn unknown() -> CompilerInfo {
+        CompilerInfo { vendor: "unknown".to_string(), ..Default::default() }
+    }
+
+    fn version_at_least(&self, minimum: &str) -> bool {
+        compare_versions(&self.version, minimum) != std::cmp::Ordering::Less
+    }
+
+    /// Checks that the compiler knows a language standard. Compilers which
+    /// only know the draft name, such as `c++2a` on GCC 8, get that name.
+    pub fn check_standard(&self, std: &LanguageStandard, cxx: bool) -> Result<LanguageStandard, String> {
+        let Some((since, draft)) = standard_support(&self.vendor, &std.version, cxx) else {
+            return Ok(std.clone());
+        };
+
+        if self.version.is_empty() || self.version_at_least(since) {
+            return Ok(std.clone());
+        }
+
+        if let Some((draft_version, draft_since)) = draft
+            && self.version_at_least(draft_since) {
+            return Ok(LanguageStandard { version: draft_version.to_string(), gnu: std.gnu });
+        }
+
+        let minimum = draft.map(|(_, draft_since)| draft_since).unwrap_or(since);
+        Err(format!("{} {} needs {} {} or newer, but the compiler is {} {}. Use a newer compiler or an older standard",
+            if cxx { "cxx_std" } else { "c_std" }, std.version, self.vendor, minimum, self.vendor, self.version))
+    }
+}
+
+// the first version of a vendor accepting a standard, and the draft name with its first version
+fn standard_support(vendor: &str, version: &str, cxx: bool) -> Option<(&'static str, Option<(&'static str, &'static str)>)> {
+    let support = match (vendor, cxx, version) {
+        ("gcc", true, "11")  => ("4.8.1", None),
+        ("gcc", true, "14")  => ("5", None),
+        ("gcc", true, "17")  => ("7", Some(("1z", "5"))),
+        ("gcc", true, "20")  => ("10", Some(("2a", "8"))),
+        ("gcc", true, "23")  => ("11", Some(("2b", "11"))),
+        ("gcc", true, "26")  => ("14", Some(("2c", "14"))),
+        ("gcc", false, "11") => ("4.7", None),
+        ("gcc", false, "17") => ("8", None),
+        ("gcc", false, "23") => ("14", Some(("2x", "9"))),
+
+        ("clang", true, "11")  => ("3.3", None),
+        ("clang", true, "14")  => ("3.5", None),
+        ("clang", true, "17")  => ("5", Some(("1z", "3.5"))),
+        ("clang", true, "20")  => ("10", Some(("2a", "5"))),
+        ("clang", true, "23")  => ("17", Some(("2b", "13"))),
+        ("clang", true, "26")  => ("19", Some(("2c", "17"))),
+        ("clang", false, "17") => ("6", None),
+        ("clang", false, "23") => ("18", Some(("2x", "9"))),
+
+        // Apple numbers its clang after Xcode, such as 15 for the LLVM 16 of Xcode 15
+        ("apple-clang", true, "11")  => ("5", None),
+        ("apple-clang", true, "14")  => ("6", None),
+        ("apple-clang", true, "17")  => ("9.1", Some(("1z", "6.1"))),
+        ("apple-clang", true, "20")  => ("12", Some(("2a", "10"))),
+        ("apple-clang", true, "23")  => ("16", Some(("2b", "13.1"))),
+        ("apple-clang", true, "26")  => ("17", Some(("2c", "16"))),
+        ("apple-clang", false, "17") => ("10", None),
+        ("apple-clang", false, "23") => ("17", Some(("2x", "11"))),
+
+        ("msvc", true, "17")  => ("19.11", None),
+        ("msvc", true, "20")  => ("19.29", None),
+        ("msvc", false, "11") => ("19.28", None),
+        ("msvc", false, "17") => ("19.28", None),
+        _ => return None,
+    };
+
+    Some(support)
+}
+
+fn run(words: &[String], args: &[&str]) -> Option<String> {
+    let output = Command::new(words.first()?)
+        .args(&words[1..])
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+
+    let text = [output.stdout, output.stderr].concat();
+    Some(String::from_utf8_lossy(&text).to_string())
+}
+
+fn parse_macros(macros: &str) -> CompilerInfo {
+    let get = |name: &str| {
+        macros.lines().find_map(|line| {
+            let rest = line.strip_prefix("#define ")?.strip_prefix(name)?;
+            rest.starts_with(' ').then(|| rest.trim().to_string())
+        })
+    };
+
+    let (vendor, version) = match (get("__clang_major__"), get("__GNUC__")) {
+        (Some(major), _) => {
+            let vendor = if get("__apple_build_version__").is_some() { "apple-clang" } else { "clang" };
+            let version = format!("{}.{}.{}", major,
+                get("__clang_minor__").unwrap_or_default(), get("__clang_patchlevel__").unwrap_or_default());
+            (vendor, version)
+        }
+        (None, Some(major)) => ("gcc", format!("{}.{}.{}", major,
+            get("__GNUC_MINOR__").unwrap_or_default(), get("__GNUC_PATCHLEVEL__").unwrap_or_default())),
+        _ => ("unknown", String::new()),
+    };
+
+    // 201703L is C++17
+    let default_cxx_std = get("__cplusplus").and_then(|value| match value.trim_end_matches('L') {
+        "199711" => Some("98".to_string()),
+        value if value.len() == 6 => Some(value[2..4].to_string()),
+        _ => None,
+    });
+
+    CompilerInfo {
+        vendor: vendor.to_string(),
+        version,
+        target: None,
+        default_cxx_std,
+    }
+}
+
+// "Microsoft (R) C/C++ Optimizing Compiler Version 19.38.33130 for x64"
+fn parse_msvc_banner(banner: &str) -> CompilerInfo {
+    let Some(line) = banner.lines().find(|line| line.contains("Version ")) else {
+        return CompilerInfo::unknown();
+    };
+
+    let after = &line[line.find("Version ").unwrap() + "Version ".len()..];
+    let mut words = after.split_whitespace();
+    let version = words.next().unwrap_or_default().to_string();
+    let target = match (words.next(), words.next()) {
+        (Some("for"), Some(arch)) => Some(arch.to_string()),
+        _ => None,
+    };
+
+    CompilerInfo {
+        vendor: "msvc".to_string(),
+        version,
+        target,
+        default_cxx_std: Some("14".to_string()),
+    }
+}
+
+fn parse_clang_version(output: &str) -> Option<String> {
+    let after = &output[output.find("clang version ")? + "clang version ".len()..];
+    after.split_whitespace().next().map(str::to_string)
+}
+
+fn compiler_modified(compiler: &str) -> u64 {
+    env::program(compiler)
+        .and_then(|program| which::which(program).ok())
+        .and_then(|path| path.metadata().ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Compares dotted versions. Only the components given on both sides count,
+/// so `13.2.0` equals `13`.
+pub fn compare_versions(version: &str, other: &str) -> std::cmp::Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['.', '-'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+
+    let (version, other) = (parse(version), parse(other));
+    let len = version.len().min(other.len());
+    version[..len].cmp(&other[..len])
+}
+
+#[test]
+fn test_compiler_info() {
+    let info = parse_macros("#define __GNUC__ 9\n#define __GNUC_MINOR__ 4\n#define __GNUC_PATCHLEVEL__ 0\n#define __cplusplus 201402L\n");
+    assert_eq!((info.vendor.as_str(), info.version.as_str()), ("gcc", "9.4.0"));
+    assert_eq!(info.default_cxx_std.as_deref(), Some("14"));
+
+    let cxx23 = LanguageStandard::parse("23").unwrap();
+    assert!(info.check_standard(&cxx23, true).unwrap_err().contains("needs gcc 11 or newer"));
+    assert_eq!(info.check_standard(&LanguageStandard::parse("20").unwrap(), true).unwrap().version, "2a");
+    assert_eq!(info.check_standard(&LanguageStandard::parse("17").unwrap(), true).unwrap().version, "17");
+
+    let info = parse_macros("#define __clang_major__ 15\n#define __clang_minor__ 0\n#define __clang_patchlevel__ 0\n#define __apple_build_version__ 15000040\n");
+    assert_eq!((info.vendor.as_str(), info.version.as_str()), ("apple-clang", "15.0.0"));
+    assert_eq!(info.check_standard(&cxx23, true).unwrap().version, "2b");
+    assert!(info.check_standard(&LanguageStandard::parse("26").unwrap(), true).unwrap_err().contains("needs apple-clang 16 or newer"));
+
+    let info = parse_msvc_banner("Microsoft (R) C/C++ Optimizing Compiler Version 19.38.33130 for x64\n");
+    assert_eq!((info.version.as_str(), info.target.as_deref()), ("19.38.33130", Some("x64")));
+
+    assert_eq!(compare_versions("13.2.0", "13"), std::cmp::Ordering::Equal);
+    assert_eq!(compare_versions("4.8.1", "4.10"), std::cmp::Ordering::Less);
+}
@@ -0,0 +1,323 @@
+use std::{path::Path, process::{Command, Stdio}};
+
+use crate::{cli::arg::get_args, cxon::CxonConfig, toolchain::{TargetType, ToolchainFamily, Toolchain, env::{self, Origin}, is_toolchain_file, msvc, wasm, zig}};
+
+/// The toolchain to build with. When the compilers are customized through
+/// cxon.json, the environment or the command line, the family of flags is
+/// taken from the compilers themselves, so that `"cxx": "clang++-18"` works
+/// with `"toolchain": "gnu"`. Toolchain files are always used as they are.
+/// Without a toolchain field, the toolchain is found in PATH.
+pub fn resolve_toolchain(cxon: &CxonConfig) -> Toolchain {
+    let mut toolchain = select_toolchain(cxon);
+    apply_cross_compilation(&mut toolchain, cxon);
+    check_linker_flavor(&toolchain, cxon);
+    toolchain
+}
+
+fn select_toolchain(cxon: &CxonConfig) -> Toolchain {
+    check_custom_tools(cxon);
+
+    let detected = detect_custom_compilers(cxon);
+
+    let Some(toolchain) = &cxon.toolchain else {
+        return match detected {
+            Some((family, compiler)) => {
+                let toolchain = family_preset(family, &compiler);
+                println!("Using the {} toolchain of {}", toolchain.name, compiler);
+                toolchain
+            }
+            None => find_toolchain(&cxon.get_target_type()),
+        };
+    };
+
+    let configured = Toolchain::from_config(toolchain, &get_args().project_dir);
+
+    match detected {
+        Some((family, compiler)) if family != configured.family && is_toolchain_file(toolchain) => {
+            println!("Warning: {} is a {} compiler but the {} toolchain uses {} flags",
+                compiler, family.name(), configured.name, configured.family.name());
+            configured
+        }
+        // msvc differs in every rule, not only in the flags
+        Some((family, compiler)) if family != configured.family
+            && (family == ToolchainFamily::MSVC || configured.family == ToolchainFamily::MSVC) => panic!(
+            "{} is a {} compiler but the {} toolchain uses {} flags, set toolchain to match the compiler or remove it to detect the toolchain",
+            compiler, family.name(), configured.name, configured.family.name()),
+        Some((family, compiler)) if family != configured.family => {
+            println!("{} is a {} compiler, using {} flags with the other tools of the {} toolchain",
+                compiler, family.name(), family.name(), configured.name);
+            with_flag_family(configured, family)
+        }
+        _ => configured,
+    }
+}
+
+// the tools of a toolchain, such as its cc and archiver, with the flags of another family
+fn with_flag_family(toolchain: Toolchain, family: ToolchainFamily) -> Toolchain {
+    let preset = family.preset();
+
+    Toolchain {
+        family,
+        compile: preset.compile,
+        flags: preset.flags,
+        ..toolchain
+    }
+}
+
+// clang-cl takes msvc flags but comes with lld-link and llvm-lib, while
+// emcc and zig cc take clang flags but come with their own tools
+fn family_preset(family: ToolchainFamily, compiler: &str) -> Toolchain {
+    match (family, program_stem(compiler).as_str()) {
+        (ToolchainFamily::MSVC, stem) if stem.starts_with("clang-cl") => msvc::clang_cl(),
+        (ToolchainFamily::LLVM, "emcc" | "em++") => wasm::toolchain(),
+        (ToolchainFamily::LLVM, "zig") => zig::toolchain(),
+        _ => family.preset(),
+    }
+}
+
+/// Points the tools of a toolchain at a target. GNU cross compilers are
+/// named after the target, such as `aarch64-linux-gnu-gcc`, while clang
+/// and clang-cl take `--target=` and archive with llvm-ar and llvm-lib.
+/// `tool_prefix` overrides the derived prefix.
+fn apply_cross_compilation(toolchain: &mut Toolchain, cxon: &CxonConfig) {
+    if cxon.target.is_none() && cxon.tool_prefix.is_none() {
+        return;
+    }
+
+    // only gnu finds its cross tools by name
+    if toolchain.family == ToolchainFamily::MSVC && toolchain.flags.target.is_none() {
+        panic!("The {} toolchain can't cross compile with target or tool_prefix, use clang-cl or the cl of a developer prompt for the target instead", toolchain.name);
+    }
+    if toolchain.family == ToolchainFamily::LLVM && toolchain.flags.target.is_none() {
+        panic!("The {} toolchain can't cross compile with target or tool_prefix", toolchain.name);
+    }
+
+    let prefix = rename_cross_tools(toolchain, cxon.target.as_deref(), cxon.tool_prefix.as_deref());
+
+    // the customized tools were checked already
+    for (tool, customized) in [(&toolchain.cc, cxon.cc.is_some()), (&toolchain.cxx, cxon.cxx.is_some())] {
+        if !customized && env::program(tool).is_none_or(|program| which::which(program).is_err()) {
+            panic!("Failed to find {} for the target {}", tool, cxon.target.as_deref().unwrap_or(&prefix));
+        }
+    }
+}
+
+// prefixes the compilers, the archiver and the linkers named after them, and returns the prefix
+fn rename_cross_tools(toolchain: &mut Toolchain, target: Option<&str>, tool_prefix: Option<&str>) -> String {
+    let prefix = match (tool_prefix, target, toolchain.family) {
+        (Some(prefix), _, _) => prefix.to_string(),
+        (None, Some(target), ToolchainFamily::GNU) => format!("{}-", target),
+        _ => String::new(),
+    };
+
+    // the ar of the host may not know the objects of the target, zig ar does
+    if toolchain.family == ToolchainFamily::LLVM && target.is_some() && prefix.is_empty() && toolchain.static_lib.linker == "ar" {
+        toolchain.static_lib.linker = "llvm-ar".to_string();
+    }
+
+    let (cc, cxx, ar) = (toolchain.cc.clone(), toolchain.cxx.clone(), toolchain.static_lib.linker.clone());
+    let prefixed = |tool: &str| format!("{}{}", prefix, tool);
+
+    for rule in [&mut toolchain.executable, &mut toolchain.static_lib, &mut toolchain.shared_lib, &mut toolchain.object_lib] {
+        if [&cc, &cxx, &ar].contains(&&rule.linker) {
+            rule.linker = prefixed(&rule.linker);
+        }
+    }
+    toolchain.cc = prefixed(&cc);
+    toolchain.cxx = prefixed(&cxx);
+
+    prefix
+}
+
+// the flag family of the compilers given by cxon.json, the environment or the command line
+fn detect_custom_compilers(cxon: &CxonConfig) -> Option<(ToolchainFamily, String)> {
+    let compilers: Vec<String> = [
+        env::resolve_tool("CC", cxon.cc.as_deref(), ""),
+        env::resolve_tool("CXX", cxon.cxx.as_deref(), ""),
+    ]
+    .into_iter()
+    .filter(|(_, origin)| *origin != Origin::Default)
+    .map(|(compiler, _)| compiler)
+    .collect();
+
+    let mut detected: Option<(ToolchainFamily, String)> = None;
+    for compiler in compilers {
+        let Some(family) = detect_family(&compiler) else {
+            println!("Unable to tell the flag family of {}", compiler);
+            continue;
+        };
+
+        match &detected {
+            Some((other, other_compiler)) if *other != family => panic!(
+                "{} is a {} compiler but {} is a {} compiler, they can't be used together",
+                other_compiler, other.name(), compiler, family.name()),
+            _ => detected = Some((family, compiler)),
+        }
+    }
+
+    detected
+}
+
+// the first toolchain in PATH, in the order usual for the platform
+fn find_toolchain(target_type: &TargetType) -> Toolchain {
+    let candidates = if cfg!(windows) {
+        [ToolchainFamily::MSVC, ToolchainFamily::LLVM, ToolchainFamily::GNU]
+    } else if cfg!(target_os = "macos") {
+        [ToolchainFamily::LLVM, ToolchainFamily::GNU, ToolchainFamily::MSVC]
+    } else {
+        [ToolchainFamily::GNU, ToolchainFamily::LLVM, ToolchainFamily::MSVC]
+    };
+
+    for family in candidates {
+        let toolchain = family.preset();
+        if toolchain.check_availability(target_type).is_ok() {
+            println!("No toolchain given, using {} found in PATH", toolchain.name);
+            return toolchain;
+        }
+    }
+
+    let missing: Vec<String> = candidates
+        .iter()
+        .filter_map(|family| family.preset().check_availability(target_type).err())
+        .collect();
+    panic!("No toolchain found in PATH, set the toolchain field or CXX:\n  {}", missing.join("\n  "));
+}
+
+// the customized tools have to exist, the launcher of `ccache gcc` included
+fn check_custom_tools(cxon: &CxonConfig) {
+    let tools = [
+        ("cc", env::resolve_tool("CC", cxon.cc.as_deref(), "")),
+        ("cxx", env::resolve_tool("CXX", cxon.cxx.as_deref(), "")),
+        ("archiver", env::resolve_tool("AR", cxon.archiver.as_deref(), "")),
+        ("linker", (cxon.get_linker_program().unwrap_or_default().to_string(), Origin::Config)),
+    ];
+
+    for (field, (tool, origin)) in tools {
+        for program in env::split_flags(&tool).iter().take(1) {
+            if which::which(program).is_err() {
+                panic!("Failed to find {} {} given by the {}", field, program, origin);
+            }
+        }
+    }
+}
+
+// the compiler drivers look for ld.mold for -fuse-ld=mold, ld64.lld on macOS,
+// while zig comes with its own lld
+fn check_linker_flavor(toolchain: &Toolchain, cxon: &CxonConfig) {
+    let Some(flavor) = cxon.get_linker_flavor() else {
+        return;
+    };
+    // archives are never linked
+    if matches!(cxon.get_target_type(), TargetType::StaticLib | TargetType::ObjectLib) {
+        return;
+    }
+
+    // fails for the toolchains which can't select a linker
+    cxon.get_fuse_ld_args(toolchain);
+
+    if program_stem(&cxon.get_link_driver(toolchain, &toolchain.executable.linker)) == "zig" {
+        if flavor != "lld" {
+            panic!("zig only links with its own lld, the {} linker given by the cxon.json can't be used", flavor);
+        }
+        return;
+    }
+
+    if which::which(format!("ld.{}", flavor)).is_err() && which::which(format!("ld64.{}", flavor)).is_err() {
+        panic!("Failed to find ld.{} for the {} linker given by the cxon.json", flavor, flavor);
+    }
+}
+
+/// Tells the flag family of a compiler from its `--version` output, or from
+/// `/?` for cl-style compilers which don't understand `--version`.
+pub fn detect_family(compiler: &str) -> Option<ToolchainFamily> {
+    let words = env::split_flags(compiler);
+    if words.is_empty() {
+        return None;
+    }
+
+    // clang-cl takes msvc style flags but answers --version like clang
+    if is_clang_cl(compiler) {
+        return Some(ToolchainFamily::MSVC);
+    }
+
+    if let Some(output) = probe(&words, "--version").map(|output| output.to_lowercase()) {
+        if output.contains("clang") {
+            return Some(ToolchainFamily::LLVM);
+        }
+        if output.contains("gcc") || output.contains("g++") || output.contains("free software foundation") {
+            return Some(ToolchainFamily::GNU);
+        }
+    }
+
+    match probe(&words, "/?") {
+        Some(output) if output.contains("Microsoft") => Some(ToolchainFamily::MSVC),
+        _ => None,
+    }
+}
+
+// such as clang-cl-18 or C:/LLVM/bin/clang-cl.exe, a launcher may come first
+pub fn is_clang_cl(compiler: &str) -> bool {
+    program_stem(compiler).starts_with("clang-cl")
+}
+
+fn program_stem(compiler: &str) -> String {
+    env::program(compiler)
+        .and_then(|program| Path::new(&program).file_stem().map(|stem| stem.to_string_lossy().to_lowercase()))
+        .unwrap_or_default()
+}
+
+fn probe(words: &[String], arg: &str) -> Option<String> {
+    let output = Command::new(&words[0])
+        .args(&words[1..])
+        .arg(arg)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+
+    let text = [output.stdout, output.stderr].concat();
+    Some(String::from_utf8_lossy(&text).to_string())
+}
+
+#[test]
+fn test_detect_family() {
+    if which::which("gcc").is_ok() {
+        assert_eq!(detect_family("gcc"), Some(ToolchainFamily::GNU));
+    }
+    if which::which("clang").is_ok() {
+        assert_eq!(detect_family("clang"), Some(ToolchainFamily::LLVM));
+    }
+    assert_eq!(detect_family("clang-cl"), Some(ToolchainFamily::MSVC));
+    assert_eq!(family_preset(ToolchainFamily::MSVC, "ccache /usr/bin/clang-cl-18").name, "clang-cl");
+    assert_eq!(family_preset(ToolchainFamily::MSVC, "cl").name, "msvc");
+
+    // `"toolchain": "gnu"` with `"cxx": "clang++"` keeps gcc and ar
+    let mixed = with_flag_family(ToolchainFamily::GNU.preset(), ToolchainFamily::LLVM);
+    assert_eq!((mixed.name.as_str(), mixed.cc.as_str(), mixed.static_lib.linker.as_str()), ("gnu", "gcc", "ar"));
+    assert_eq!((mixed.family, mixed.flags.time_trace.as_deref()), (ToolchainFamily::LLVM, Some("-ftime-trace")));
+    assert_eq!(family_preset(ToolchainFamily::LLVM, "/emsdk/upstream/emscripten/em++").name, "wasm");
+    assert_eq!(family_preset(ToolchainFamily::LLVM, "ccache zig c++").name, "zig");
+}
+
+#[test]
+fn test_rename_cross_tools() {
+    let tools = |toolchain: &Toolchain| [toolchain.cc.clone(), toolchain.cxx.clone(),
+        toolchain.static_lib.linker.clone(), toolchain.executable.linker.clone()];
+
+    let mut gnu = ToolchainFamily::GNU.preset();
+    assert_eq!(rename_cross_tools(&mut gnu, Some("aarch64-linux-gnu"), None), "aarch64-linux-gnu-");
+    assert_eq!(tools(&gnu), ["aarch64-linux-gnu-gcc", "aarch64-linux-gnu-g++", "aarch64-linux-gnu-ar", "aarch64-linux-gnu-g++"]);
+
+    let mut gnu = ToolchainFamily::GNU.preset();
+    rename_cross_tools(&mut gnu, Some("arm-none-eabi"), Some("/opt/arm/bin/arm-none-eabi-"));
+    assert_eq!(gnu.cc, "/opt/arm/bin/arm-none-eabi-gcc");
+
+    let mut llvm = ToolchainFamily::LLVM.preset();
+    assert_eq!(rename_cross_tools(&mut llvm, Some("aarch64-linux-gnu"), None), "");
+    assert_eq!(tools(&llvm), ["clang", "clang++", "llvm-ar", "clang++"]);
+
+    let cxon: CxonConfig = serde_json::from_value(serde_json::json!({
+        "project": "app", "target": "aarch64-linux-gnu", "sysroot": "/opt/sysroot"
+    })).unwrap();
+    assert_eq!(cxon.get_cross_args(&llvm), ["--target=aarch64-linux-gnu", "--sysroot=/opt/sysroot"]);
+    assert_eq!(cxon.get_cross_args(&gnu), ["--sysroot=/opt/sysroot"]);
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use crate::{cli::arg::get_args, cxon::CxonConfig, toolchain::Toolchain};
+
+// Programs starting the compiler given after them
+const LAUNCHERS: [&str; 4] = ["ccache", "sccache", "distcc", "icecc"];
+
+// The variables of make and autotools that packagers and CI images set
+pub const ENV_VARS: [&str; 7] = ["CC", "CXX", "AR", "CFLAGS", "CXXFLAGS", "CPPFLAGS", "LDFLAGS"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Cli,
+    Env,
+    Config,
+    Default,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Origin::Cli => "command line",
+            Origin::Env => "environment",
+            Origin::Config => "cxon.json",
+            Origin::Default => "toolchain default",
+        })
+    }
+}
+
+// `NAME=value` on the command line takes precedence over the environment
+pub fn lookup(name: &str) -> Option<(String, Origin)> {
+    if let Some(value) = get_args().env_overrides.get(name) {
+        return Some((value.clone(), Origin::Cli));
+    }
+
+    std::env::var(name)
+        .ok()
+        .map(|value| (value, Origin::Env))
+}
+
+/// Resolves a tool such as CC in the order: command line, environment,
+/// cxon.json and the toolchain default. Empty values are ignored.
+pub fn resolve_tool(name: &str, config: Option<&str>, default: &str) -> (String, Origin) {
+    if let Some((value, origin)) = lookup(name)
+        && !value.trim().is_empty() {
+        return (value, origin);
+    }
+
+    match config {
+        Some(value) if !value.trim().is_empty() => (value.to_string(), Origin::Config),
+        _ => (default.to_string(), Origin::Default),
+    }
+}
+
+// flags such as CFLAGS, appended after the flags of cxon.json
+pub fn flags(name: &str) -> Vec<String> {
+    lookup(name)
+        .map(|(value, _)| split_flags(&value))
+        .unwrap_or_default()
+}
+
+// the effective compilers and flags, printed with -v
+pub fn print_effective(cxon: &CxonConfig, toolchain: &Toolchain) {
+    let (cc, cc_origin) = cxon.get_cc(toolchain);
+    let (cxx, cxx_origin) = cxon.get_cxx(toolchain);
+    let (archiver, archiver_origin) = cxon.get_archiver(toolchain);
+    let cflags = [cxon.get_setting_args(toolchain, true), cxon.get_cflags()].concat();
+    let cxxflags = [cxon.get_setting_args(toolchain, false), cxon.get_cxxflags()].concat();
+
+    println!("  CC       {} ({})", cc, cc_origin);
+    println!("  CXX      {} ({})", cxx, cxx_origin);
+    let info = cxon.get_compiler_info();
+    println!("  COMPILER {} {} for {}, c++{} by default", info.vendor, info.version,
+        info.target.as_deref().unwrap_or("unknown target"), info.default_cxx_std.as_deref().unwrap_or("?"));
+    println!("  AR       {} ({})", archiver, archiver_origin);
+    let ldflags = [cxon.get_fuse_ld_args(toolchain), cxon.get_ldflags()].concat();
+
+    println!("  LINKER   {}", cxon.get_link_driver(toolchain, &toolchain.executable.linker));
+    println!("  CFLAGS   {}", cflags.join(" "));
+    println!("  CXXFLAGS {}", cxxflags.join(" "));
+    println!("  LDFLAGS  {}", ldflags.join(" "));
+
+    for name in ["CPPFLAGS", "CFLAGS", "CXXFLAGS", "LDFLAGS"] {
+        if let Some((value, origin)) = lookup(name) {
+            println!("  {} from the {}: {}", name, origin, value);
+        }
+    }
+}
+
+/// The program of a tool such as `ccache gcc` or `zig cc`, which is the
+/// first word after the launchers.
+pub fn program(tool: &str) -> Option<String> {
+    split_flags(tool).into_iter().find(|word| {
+        let stem = std::path::Path::new(word)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        !LAUNCHERS.contains(&stem.as_str())
+    })
+}
+
+/// Splits a command line the way a POSIX shell would for the simple cases
+/// found in these variables: whitespace, single and double quotes, and
+/// backslash escapes.
+pub fn split_flags(value: &str) -> Vec<String> {
+    let mut flags = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    flags.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        panic!("Unterminated quote in {}", value);
+    }
+    if in_word {
+        flags.push(current);
+    }
+
+    flags
+}
+
+#[test]
+fn test_split_flags() {
+    assert_eq!(split_flags("  -O2   -g "), ["-O2", "-g"]);
+    assert_eq!(split_flags(r#"-DNAME="a b" '-DX=$y' -I\ dir"#), ["-DNAME=a b", "-DX=$y", "-I dir"]);
+    assert_eq!(split_flags("ccache gcc"), ["ccache", "gcc"]);
+    assert!(split_flags("").is_empty());
+
+    assert_eq!(program("ccache /usr/bin/gcc-12").as_deref(), Some("/usr/bin/gcc-12"));
+    assert_eq!(program("sccache zig c++").as_deref(), Some("zig"));
+    assert_eq!(program("ccache"), None);
+}
@@ -1,32 +1,71 @@
-use crate::toolchain::ToolChainTrait;
+use crate::toolchain::Toolchain;
 
-pub struct GNU{
-    #[allow(dead_code)] data: ()
-}
+const PRESET: &str = r#"
+name = "gnu"
+family = "gnu"
+cc = "gcc"
+cxx = "g++"
+
+[compile]
+args = ["-c", "{source}", "-o", "{output}"]
+object_extension = "o"
+
+[executable]
+linker = "g++"
+args = ["{objects}", "-o", "{output}"]
+extension = ""
+
+[static_lib]
+linker = "ar"
+args = ["rcs", "{output}", "{objects}"]
+extension = "a"
+
+[shared_lib]
+linker = "g++"
+args = ["-shared", "-fPIC", "-o", "{output}", "{objects}"]
+extension = "so"
+
+[object_lib]
+linker = "lr"
+args = ["{objects}", "-o", "{output}"]
+extension = "o"
+
+[flags]
+define_prefix = "-D"
+include_prefix = "-I"
+link_dir_prefix = "-L"
+link_lib_prefix = "-l"
+debug = "-g"
+sysroot = "--sysroot={sysroot}"
+fuse_ld = "-fuse-ld={linker}"
 
-impl ToolChainTrait for GNU {
-    const CC:     &'static str = "gcc";
-    const CXX:    &'static str = "g++";
-    const DEBUG_FLAG:  &'static str = "-g";
-
-    const EXECUTABLE_LINKER: &'static str = "g++";
-    const STATIC_LIB_LINKER: &'static str = "ar";
-    const SHARED_LIB_LINKER: &'static str = "g++";
-    const OBJECT_LIB_LINKER: &'static str = "lr";
-
-    const EXECUTABLE_OUTPUT_FLAG: &'static str = "-o";
-    const STATIC_LIB_OUTPUT_FLAG: &'static str = "rcs";
-    const SHARED_LIB_OUTPUT_FLAG: &'static str = "-shared -fPIC -o";
-    const OBJECT_LIB_OUTPUT_FLAG: &'static str = "-o";
-
-    const EXECUTABLE_EXTENSION: &'static str = "";
-    const STATIC_LIB_EXTENSION: &'static str = "a";
-    const SHARED_LIB_EXTENSION: &'static str = "so";
-    const OBJECT_LIB_EXTENSION: &'static str = "o";
-
-    const ONLY_COMPILE_FLAG:    &'static str = "-c";
-    const DEFINE_FLAG_PREFIX:   &'static str = "-D";
-    const INCLUDE_FLAG_PREFIX:  &'static str = "-I";
-    const LINK_DIR_FLAG_PREFIX: &'static str = "-L";
-    const LINK_LIB_FLAG_PREFIX: &'static str = "-l";
-}
\ No newline at end of file
+[flags.optimization]
+none = ["-O0"]
+size = ["-Os"]
+speed = ["-O2"]
+max = ["-O3"]
+
+[flags.warnings]
+none = ["-w"]
+default = []
+all = ["-Wall"]
+extra = ["-Wall", "-Wextra"]
+error = ["-Wall", "-Wextra", "-Werror"]
+
+[flags.debug_info]
+none = []
+minimal = ["-g1"]
+full = ["-g"]
+
+[flags.c_std]
+flag = "-std=c{version}"
+gnu_flag = "-std=gnu{version}"
+
+[flags.cxx_std]
+flag = "-std=c++{version}"
+gnu_flag = "-std=gnu++{version}"
+"#;
+
+pub fn toolchain() -> Toolchain {
+    Toolchain::from_toml(PRESET).expect("Invalid gnu toolchain preset")
+}
@@ -1,109 +1,131 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, process::Command};
 
-use crate::{cxon::get_cxon_config, object::output::ObjectCollection, toolchain::{TargetType, ToolChainTrait}};
+use crate::{build::jobserver::get_jobserver, cxon::get_cxon_config, object::output::ObjectCollection, toolchain::{TargetType, ToolchainFamily, Toolchain, env, get_toolchain, msvc, response_file::{self, Quoting}}};
 
 struct LinkArgs {
     pub linker: String,
     pub output_path: PathBuf,
 
-    pub output_flag: String,
     pub other_flags: Vec<String>,
     pub link_dir_args: Vec<String>,
     pub link_lib_args: Vec<String>,
 }
 
-pub fn link<T: ToolChainTrait>(input: ObjectCollection, target_type: TargetType) -> () {
-    let output_dir = &get_cxon_config().read().unwrap().output_dir;
-    let target_name = &get_cxon_config().read().unwrap().get_target_name();
-    let output_path = output_dir.join(PathBuf::from(target_name));
+// returns the path of the linked artifact
+pub fn link(input: ObjectCollection, target_type: TargetType) -> PathBuf {
+    let cxon = get_cxon_config().read().unwrap();
+    let toolchain = get_toolchain();
+    let rule = toolchain.link_rule(&target_type);
+
+    let output_path = cxon.output_dir.join(PathBuf::from(cxon.get_target_name()));
+    let output_path = match target_type {
+        TargetType::Executable => output_path.with_added_extension(cxon.get_executable_extension(toolchain)),
+        _ => output_path.with_extension(&rule.extension),
+    };
 
     let mut other_flags = Vec::new();
 
-    // debug flag
-    if get_cxon_config().read().unwrap().get_debug_flag() {
-        other_flags.push(T::DEBUG_FLAG.to_string());
+    let linker = match target_type {
+        TargetType::Executable | TargetType::SharedLib => {
+            // the archivers don't take the debug flag
+            if cxon.get_debug_flag() {
+                other_flags.push(toolchain.flags.debug.clone());
+            }
+            // ldflags and LDFLAGS only apply to the links done by the compiler driver
+            other_flags.extend(cxon.get_cross_args(toolchain));
+            other_flags.extend(cxon.get_fuse_ld_args(toolchain));
+            other_flags.extend(cxon.get_link_setting_args(toolchain));
+            other_flags.extend(cxon.get_ldflags());
+            cxon.get_link_driver(toolchain, &rule.linker)
+        }
+        TargetType::StaticLib => cxon.get_archiver(toolchain).0,
+        TargetType::ObjectLib => rule.linker.clone(),
+    };
+
+    let args = LinkArgs {
+        linker,
+        output_path,
+        other_flags,
+        link_dir_args: cxon.get_link_dir_args(toolchain),
+        link_lib_args: cxon.get_lib_args(toolchain),
+    };
+
+    let mut command_line = link_command_line(toolchain, &target_type, &input.to_args(), &args);
+
+    // large libraries have more objects than a command line takes
+    if response_file::is_too_long(&command_line) {
+        let rsp_path = cxon.build_dir.join(cxon.get_target_name()).with_added_extension("rsp");
+        let objects = [response_file::write(&rsp_path, &input.to_args(), Quoting::of(toolchain.family))];
+        command_line = link_command_line(toolchain, &target_type, &objects, &args);
     }
 
-    match target_type {
-        TargetType::Executable => link_to_executable_cmd::<T>(input, LinkArgs {
-            linker:        T::EXECUTABLE_LINKER.to_string(),
-            output_path,
-            output_flag:   T::EXECUTABLE_OUTPUT_FLAG.to_string(),
-            link_dir_args: get_cxon_config().read().unwrap().get_link_dir_args::<T>(),
-            link_lib_args: get_cxon_config().read().unwrap().get_lib_args::<T>(),
-            other_flags,
-        }),
-        TargetType::StaticLib  => link_to_static_lib_cmd::<T>(input, LinkArgs {
-            linker:        T::STATIC_LIB_LINKER.to_string(),
-            output_path,
-            output_flag:   T::STATIC_LIB_OUTPUT_FLAG.to_string(),
-            link_dir_args: get_cxon_config().read().unwrap().get_link_dir_args::<T>(),
-            link_lib_args: get_cxon_config().read().unwrap().get_lib_args::<T>(),
-            other_flags,
-        }),
-        TargetType::SharedLib  => link_to_shared_lib_cmd::<T>(input, LinkArgs {
-            linker:        T::SHARED_LIB_LINKER.to_string(),
-            output_path,
-            output_flag:   T::SHARED_LIB_OUTPUT_FLAG.to_string(),
-            link_dir_args: get_cxon_config().read().unwrap().get_link_dir_args::<T>(),
-            link_lib_args: get_cxon_config().read().unwrap().get_lib_args::<T>(),
-            other_flags,
-        }),
-        TargetType::ObjectLib  => link_to_object_cmd::<T>(input, LinkArgs {
-            linker:        T::OBJECT_LIB_LINKER.to_string(),
-            output_path,
-            output_flag:   T::OBJECT_LIB_OUTPUT_FLAG.to_string(),
-            link_dir_args: get_cxon_config().read().unwrap().get_link_dir_args::<T>(),
-            link_lib_args: get_cxon_config().read().unwrap().get_lib_args::<T>(),
-            other_flags,
-        }),
+    let status = new_link_command(&command_line)
+        .status()
+        .unwrap_or_else(|err| panic!("Failed to link {}: {}", args.output_path.display(), err));
+
+    if !status.success() {
+        panic!("Failed to link {}", args.output_path.display());
     }
+
+    args.output_path
 }
 
-fn link_to_executable_cmd<T: ToolChainTrait>(input: ObjectCollection, args: LinkArgs) -> () {
-    std::process::Command::new(args.linker)
-        .args(input.to_args())
-        .arg(args.output_flag)
-        .arg(args.output_path.with_added_extension(T::EXECUTABLE_EXTENSION).to_str().unwrap())
-        .args(args.link_dir_args)
-        .args(args.link_lib_args)
-        .args(args.other_flags)
-        .status()
-        .expect(format!("Failed to link executable {}", args.output_path.to_str().unwrap()).as_str());
+// the linker and its arguments, the objects are placed by the link rule of the toolchain
+fn link_command_line(toolchain: &Toolchain, target_type: &TargetType, objects: &[String], args: &LinkArgs) -> Vec<String> {
+    let mut command_line = env::split_flags(&args.linker);
+    let program = env::program(&args.linker).unwrap_or_default();
+    let link_flags = [args.link_dir_args.as_slice(), &args.link_lib_args, &args.other_flags].concat();
+
+    command_line.extend(toolchain.link_args(target_type, objects, &args.output_path));
+    if toolchain.family == ToolchainFamily::MSVC {
+        command_line.extend(msvc::translate_link_flags(msvc::Tool::of(&program), &link_flags));
+    } else {
+        command_line.extend(link_flags);
+    }
+
+    command_line
 }
 
-fn link_to_static_lib_cmd<T: ToolChainTrait>(input: ObjectCollection, args: LinkArgs) -> () {
-    std::process::Command::new(args.linker)
-        .args(args.output_flag.split(' '))
-        .arg(args.output_path.with_extension(T::STATIC_LIB_EXTENSION).to_str().unwrap())
-        .args(input.to_args())
-        .args(args.link_dir_args)
-        .args(args.link_lib_args)
-        .args(args.other_flags)
-        .status()
-        .expect(format!("Failed to link static library {}", args.output_path.to_str().unwrap()).as_str());
+fn new_link_command(command_line: &[String]) -> Command {
+    let mut cmd = Command::new(&command_line[0]);
+    cmd.args(&command_line[1..]);
+    get_jobserver().configure(&mut cmd);
+    cmd
 }
 
-fn link_to_shared_lib_cmd<T: ToolChainTrait>(input: ObjectCollection, args: LinkArgs) -> () {
-    std::process::Command::new(args.linker)
-        .args(args.output_flag.split(' '))
-        .arg(args.output_path.with_extension(T::SHARED_LIB_EXTENSION).to_str().unwrap())
-        .args(input.to_args())
-        .args(args.link_dir_args)
-        .args(args.link_lib_args)
-        .args(args.other_flags)
-        .status()
-        .expect(format!("Failed to link shared library {}", args.output_path.to_str().unwrap()).as_str());
+#[test]
+fn test_link_command_line() {
+    let toolchain = crate::toolchain::ToolchainFamily::GNU.preset();
+    let args = LinkArgs {
+        linker: "ccache g++".to_string(),
+        output_path: PathBuf::from("bin/app"),
+        other_flags: vec!["-g".to_string()],
+        link_dir_args: vec![toolchain.link_dir_arg(&PathBuf::from("lib"))],
+        link_lib_args: vec![toolchain.link_lib_arg("m")],
+    };
+
+    assert_eq!(
+        link_command_line(&toolchain, &TargetType::Executable, &["main.o".to_string()], &args),
+        ["ccache", "g++", "main.o", "-o", "bin/app", "-Llib", "-lm", "-g"]);
 }
 
-fn link_to_object_cmd<T: ToolChainTrait>(input: ObjectCollection, args: LinkArgs) -> () {
-    std::process::Command::new(args.linker)
-        .args(input.to_args())
-        .args(args.output_flag.split(' '))
-        .arg(args.output_path.with_extension(T::OBJECT_LIB_EXTENSION).to_str().unwrap())
-        .args(args.link_dir_args)
-        .args(args.link_lib_args)
-        .args(args.other_flags)
-        .status()
-        .expect(format!("Failed to link object file {}", args.output_path.to_str().unwrap()).as_str());
-}
\ No newline at end of file
+#[test]
+fn test_msvc_link_command_line() {
+    let toolchain = crate::toolchain::ToolchainFamily::MSVC.preset();
+    let args = LinkArgs {
+        linker: "cl".to_string(),
+        output_path: PathBuf::from("bin/app.exe"),
+        other_flags: vec![toolchain.flags.debug.clone()],
+        link_dir_args: vec![toolchain.link_dir_arg(&PathBuf::from("lib"))],
+        link_lib_args: vec![toolchain.link_lib_arg("user32")],
+    };
+
+    assert_eq!(
+        link_command_line(&toolchain, &TargetType::Executable, &["main.obj".to_string()], &args),
+        ["cl", "/nologo", "main.obj", "/Febin/app.exe", "/link", "/LIBPATH:lib", "user32.lib", "/DEBUG"]);
+
+    let args = LinkArgs { linker: "lib".to_string(), output_path: PathBuf::from("bin/x.lib"), ..args };
+    assert_eq!(
+        link_command_line(&toolchain, &TargetType::StaticLib, &["a.obj".to_string()], &args),
+        ["lib", "/nologo", "/OUT:bin/x.lib", "a.obj", "/LIBPATH:lib", "user32.lib"]);
+}
@@ -1,35 +1,74 @@
-use crate::toolchain::ToolChainTrait;
+use crate::toolchain::Toolchain;
 
-pub struct LLVM {
-    #[allow(dead_code)] data: ()
-}
+const PRESET: &str = r#"
+name = "llvm"
+family = "llvm"
+cc = "clang"
+cxx = "clang++"
+
+[compile]
+args = ["-c", "{source}", "-o", "{output}"]
+object_extension = "o"
+
+[executable]
+linker = "clang++"
+args = ["{objects}", "-o", "{output}"]
+extension = ""
+
+# llvm-lib on windows
+[static_lib]
+linker = "ar"
+args = ["rcs", "{output}", "{objects}"]
+extension = "a"
+
+[shared_lib]
+linker = "clang++"
+args = ["-shared", "-fPIC", "-o", "{output}", "{objects}"]
+extension = "so"
+
+[object_lib]
+linker = "clang++"
+args = ["{objects}", "-o", "{output}"]
+extension = "o"
+
+[flags]
+define_prefix = "-D"
+include_prefix = "-I"
+link_dir_prefix = "-L"
+link_lib_prefix = "-l"
+debug = "-g"
+target = "--target={target}"
+sysroot = "--sysroot={sysroot}"
+fuse_ld = "-fuse-ld={linker}"
+time_trace = "-ftime-trace"
 
-impl ToolChainTrait for LLVM {
-    const CC:     &'static str = "clang";
-    const CXX:    &'static str = "clang++";
-    const DEBUG_FLAG:  &'static str = "-g";
-
-    const EXECUTABLE_LINKER: &'static str = "clang++";
-    // #[cfg(windows)]
-    // const STATIC_LIB_LINKER: &'static str = "llvm-lib";
-    // #[cfg(not(windows))] 
-    const STATIC_LIB_LINKER: &'static str = "ar";
-    const SHARED_LIB_LINKER: &'static str = "clang++";
-    const OBJECT_LIB_LINKER: &'static str = "clang++";
-
-    const EXECUTABLE_OUTPUT_FLAG: &'static str = "-o";
-    const STATIC_LIB_OUTPUT_FLAG: &'static str = "rcs";
-    const SHARED_LIB_OUTPUT_FLAG: &'static str = "-shared -fPIC -o";
-    const OBJECT_LIB_OUTPUT_FLAG: &'static str = "-o";
-
-    const EXECUTABLE_EXTENSION: &'static str = "";
-    const STATIC_LIB_EXTENSION: &'static str = "a";
-    const SHARED_LIB_EXTENSION: &'static str = "so";
-    const OBJECT_LIB_EXTENSION: &'static str = "o";
-
-    const ONLY_COMPILE_FLAG:    &'static str = "-c";
-    const DEFINE_FLAG_PREFIX:   &'static str = "-D";
-    const INCLUDE_FLAG_PREFIX:  &'static str = "-I";
-    const LINK_DIR_FLAG_PREFIX: &'static str = "-L";
-    const LINK_LIB_FLAG_PREFIX: &'static str = "-l";
-}
\ No newline at end of file
+[flags.optimization]
+none = ["-O0"]
+size = ["-Os"]
+speed = ["-O2"]
+max = ["-O3"]
+
+[flags.warnings]
+none = ["-w"]
+default = []
+all = ["-Wall"]
+extra = ["-Wall", "-Wextra"]
+error = ["-Wall", "-Wextra", "-Werror"]
+
+[flags.debug_info]
+none = []
+minimal = ["-gline-tables-only"]
+full = ["-g"]
+
+[flags.c_std]
+flag = "-std=c{version}"
+gnu_flag = "-std=gnu{version}"
+
+[flags.cxx_std]
+flag = "-std=c++{version}"
+gnu_flag = "-std=gnu++{version}"
+"#;
+
+pub fn toolchain() -> Toolchain {
+    Toolchain::from_toml(PRESET).expect("Invalid llvm toolchain preset")
+}
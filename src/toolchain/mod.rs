@@ -1,15 +1,56 @@
-use crate::utils;
+use std::{collections::HashMap, path::Path, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cxon::get_cxon_config, toolchain::settings::{DebugInfo, LanguageStandard, Optimization, Warnings}, utils};
 
 pub mod compiler;
+pub mod compiler_info;
+pub mod detect;
+pub mod env;
 pub mod linker;
+pub mod settings;
 pub mod gnu;
 pub mod llvm;
 pub mod msvc;
+pub mod response_file;
+pub mod wasm;
+pub mod zig;
+
+static TOOLCHAIN: LazyLock<Toolchain> = LazyLock::new(|| {
+    get_cxon_config().read().unwrap().get_toolchain().clone()
+});
+
+// the toolchain the project is built with
+pub fn get_toolchain() -> &'static Toolchain {
+    &TOOLCHAIN
+}
 
-pub enum ToolChain {
-    GNU(),
-    LLVM(),
-    MSVC(),
+// The flag family of a toolchain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolchainFamily {
+    GNU,
+    LLVM,
+    MSVC,
+}
+
+impl ToolchainFamily {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolchainFamily::GNU  => "gnu",
+            ToolchainFamily::LLVM => "llvm",
+            ToolchainFamily::MSVC => "msvc",
+        }
+    }
+
+    pub fn preset(&self) -> Toolchain {
+        match self {
+            ToolchainFamily::GNU  => gnu::toolchain(),
+            ToolchainFamily::LLVM => llvm::toolchain(),
+            ToolchainFamily::MSVC => msvc::toolchain(),
+        }
+    }
 }
 
 pub enum TargetType {
@@ -19,38 +60,338 @@ pub enum TargetType {
     SharedLib,
 }
 
-pub trait ToolChainTrait {
-    const CC:     &'static str;
-    const CXX:    &'static str;
-    const DEBUG_FLAG:  &'static str;
-
-    const EXECUTABLE_LINKER: &'static str;
-    const STATIC_LIB_LINKER: &'static str;
-    const SHARED_LIB_LINKER: &'static str;
-    const OBJECT_LIB_LINKER: &'static str;
-
-    const EXECUTABLE_OUTPUT_FLAG: &'static str;
-    const STATIC_LIB_OUTPUT_FLAG: &'static str;
-    const SHARED_LIB_OUTPUT_FLAG: &'static str;
-    const OBJECT_LIB_OUTPUT_FLAG: &'static str;
-
-    const EXECUTABLE_EXTENSION: &'static str;
-    const STATIC_LIB_EXTENSION: &'static str;
-    const SHARED_LIB_EXTENSION: &'static str;
-    const OBJECT_LIB_EXTENSION: &'static str;
-
-    const ONLY_COMPILE_FLAG:    &'static str;
-    const DEFINE_FLAG_PREFIX:   &'static str;
-    const INCLUDE_FLAG_PREFIX:  &'static str;
-    const LINK_DIR_FLAG_PREFIX: &'static str;
-    const LINK_LIB_FLAG_PREFIX: &'static str;
-}
-
-pub fn check_toolchain_availability<T: ToolChainTrait>() -> () {
-    utils::check_executable_exists(T::CC);
-    utils::check_executable_exists(T::CXX);
-    utils::check_executable_exists(T::EXECUTABLE_LINKER);
-    utils::check_executable_exists(T::STATIC_LIB_LINKER);
-    utils::check_executable_exists(T::SHARED_LIB_LINKER);
-    utils::check_executable_exists(T::OBJECT_LIB_LINKER);
+/// Everything cxon needs to know to drive a compiler, loaded from the
+/// built-in presets or from a TOML or JSON toolchain file. Arguments may
+/// contain `{source}`, `{output}` and, as a whole argument, `{objects}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Toolchain {
+    pub name: String,
+    pub family: ToolchainFamily,
+
+    pub cc:  String,
+    pub cxx: String,
+
+    pub compile: CompileRule,
+
+    pub executable: LinkRule,
+    pub static_lib: LinkRule,
+    pub shared_lib: LinkRule,
+    pub object_lib: LinkRule,
+
+    pub flags: FlagSpellings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileRule {
+    // compile only, e.g. ["-c", "{source}", "-o", "{output}"]
+    pub args: Vec<String>,
+    pub object_extension: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkRule {
+    pub linker: String,
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub extension: String,
+    // runs the executables which can't be started directly, such as ["node"]
+    #[serde(default)]
+    pub runner: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagSpellings {
+    pub define_prefix:   String,
+    pub include_prefix:  String,
+    pub link_dir_prefix: String,
+    pub link_lib_prefix: String,
+    // such as `.lib`, the libraries already ending with it are kept
+    #[serde(default)]
+    pub link_lib_suffix: String,
+
+    pub debug: String,
+    // writes a per-TU trace in Chrome trace-event format
+    pub time_trace: Option<String>,
+    // cross compilation, such as `--target={target}` and `--sysroot={sysroot}`
+    pub target: Option<String>,
+    pub sysroot: Option<String>,
+    // selects the linker named by cxon.json, such as `-fuse-ld={linker}`
+    pub fuse_ld: Option<String>,
+    // a link setting of the wasm section, such as `-s{name}={value}`
+    pub link_setting: Option<String>,
+
+    // translation of the portable settings of cxon.json
+    #[serde(default)]
+    pub optimization: HashMap<Optimization, Vec<String>>,
+    #[serde(default)]
+    pub warnings: HashMap<Warnings, Vec<String>>,
+    #[serde(default)]
+    pub debug_info: HashMap<DebugInfo, Vec<String>>,
+    pub c_std:   StandardFlag,
+    pub cxx_std: StandardFlag,
+}
+
+// A language standard flag such as `-std=c++{version}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardFlag {
+    pub flag: String,
+    // used for the gnu standards, `flag` is used when it's missing
+    pub gnu_flag: Option<String>,
+    // versions spelled differently, such as "23" = "latest"
+    #[serde(default)]
+    pub versions: HashMap<String, String>,
+}
+
+impl StandardFlag {
+    fn format(&self, std: &LanguageStandard) -> String {
+        let template = match (&self.gnu_flag, std.gnu) {
+            (Some(gnu_flag), true) => gnu_flag,
+            _ => &self.flag,
+        };
+        let version = self.versions.get(&std.version).unwrap_or(&std.version);
+
+        template.replace("{version}", version)
+    }
+}
+
+impl Toolchain {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|err| err.to_string())
+    }
+
+    /// Loads a toolchain file. `inherits = "gnu"` takes the fields which
+    /// aren't given from a built-in preset.
+    pub fn load(path: &Path) -> Toolchain {
+        let content = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Failed to read toolchain file {}: {}", path.display(), err));
+
+        let mut value: serde_json::Value = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)
+                .unwrap_or_else(|err| panic!("Failed to parse toolchain file {}: {}", path.display(), err)),
+            _ => utils::jsonc::from_str(&content)
+                .unwrap_or_else(|err| panic!("Failed to parse toolchain file {}:{}:{}: {}",
+                    path.display(), err.line, err.column, err.message)),
+        };
+
+        // named after the file unless it has a name, even when it inherits a preset
+        if let Some(object) = value.as_object_mut()
+            && !object.contains_key("name") {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            object.insert("name".to_string(), serde_json::Value::String(name));
+        }
+
+        if let Some(inherits) = value.as_object_mut().and_then(|object| object.remove("inherits")) {
+            let Some(preset) = inherits.as_str().and_then(Self::preset) else {
+                panic!("Unknown toolchain {} inherited by {}. Built-in toolchains are: gnu, llvm, msvc, clang-cl, wasm, zig", inherits, path.display());
+            };
+
+            let mut base = serde_json::to_value(preset).unwrap();
+            merge(&mut base, value);
+            value = base;
+        }
+
+        serde_json::from_value(value)
+            .unwrap_or_else(|err| panic!("Invalid toolchain file {}: {}", path.display(), err))
+    }
+
+    pub fn preset(name: &str) -> Option<Toolchain> {
+        match name.to_lowercase().as_str() {
+            "gnu"  => Some(ToolchainFamily::GNU.preset()),
+            "llvm" => Some(ToolchainFamily::LLVM.preset()),
+            "msvc" => Some(ToolchainFamily::MSVC.preset()),
+            "clang-cl" => Some(msvc::clang_cl()),
+            "wasm" => Some(wasm::toolchain()),
+            "zig"  => Some(zig::toolchain()),
+            _ => None,
+        }
+    }
+
+    // a preset based on this one, the tables of `text` are merged into it
+    pub fn inherit(&self, text: &str) -> Result<Toolchain, String> {
+        let overrides: serde_json::Value = toml::from_str(text).map_err(|err| err.to_string())?;
+
+        let mut value = serde_json::to_value(self).unwrap();
+        merge(&mut value, overrides);
+        serde_json::from_value(value).map_err(|err| err.to_string())
+    }
+
+    // a built-in name, or a toolchain file relative to the project directory
+    pub fn from_config(toolchain: &str, project_dir: &Path) -> Toolchain {
+        if let Some(preset) = Self::preset(toolchain) {
+            return preset;
+        }
+
+        let path = project_dir.join(toolchain);
+        if !is_toolchain_file(toolchain) || !path.is_file() {
+            panic!("Unsupported toolchain: {}. Use gnu, llvm, msvc, clang-cl, wasm, zig or the path of a .toml or .json toolchain file", toolchain);
+        }
+
+        Self::load(&path)
+    }
+
+    pub fn link_rule(&self, target_type: &TargetType) -> &LinkRule {
+        match target_type {
+            TargetType::Executable => &self.executable,
+            TargetType::StaticLib  => &self.static_lib,
+            TargetType::SharedLib  => &self.shared_lib,
+            TargetType::ObjectLib  => &self.object_lib,
+        }
+    }
+
+    // the arguments compiling a source file into an object file
+    pub fn compile_args(&self, source: &Path, output: &Path) -> Vec<String> {
+        expand_args(&self.compile.args, &[], source, output)
+    }
+
+    // the arguments linking objects, without the link directories, libraries and flags
+    pub fn link_args(&self, target_type: &TargetType, objects: &[String], output: &Path) -> Vec<String> {
+        expand_args(&self.link_rule(target_type).args, objects, Path::new(""), output)
+    }
+
+    pub fn define_arg(&self, define: &str) -> String {
+        format!("{}{}", self.flags.define_prefix, define)
+    }
+
+    pub fn include_arg(&self, dir: &Path) -> String {
+        format!("{}{}", self.flags.include_prefix, dir.to_str().unwrap())
+    }
+
+    pub fn link_dir_arg(&self, dir: &Path) -> String {
+        format!("{}{}", self.flags.link_dir_prefix, dir.to_str().unwrap())
+    }
+
+    pub fn link_lib_arg(&self, lib: &str) -> String {
+        let suffix = &self.flags.link_lib_suffix;
+        if suffix.is_empty() || lib.ends_with(suffix.as_str()) {
+            format!("{}{}", self.flags.link_lib_prefix, lib)
+        } else {
+            format!("{}{}{}", self.flags.link_lib_prefix, lib, suffix)
+        }
+    }
+
+    pub fn optimization_flags(&self, optimization: Optimization) -> Vec<String> {
+        self.flags.optimization.get(&optimization).cloned().unwrap_or_default()
+    }
+
+    pub fn warning_flags(&self, warnings: Warnings) -> Vec<String> {
+        self.flags.warnings.get(&warnings).cloned().unwrap_or_default()
+    }
+
+    pub fn debug_info_flags(&self, debug_info: DebugInfo) -> Vec<String> {
+        self.flags.debug_info.get(&debug_info).cloned().unwrap_or_default()
+    }
+
+    pub fn c_std_flag(&self, std: &LanguageStandard) -> String {
+        self.flags.c_std.format(std)
+    }
+
+    pub fn cxx_std_flag(&self, std: &LanguageStandard) -> String {
+        self.flags.cxx_std.format(std)
+    }
+
+    // the compilers and the linker of the target type have to be in PATH
+    pub fn check_availability(&self, target_type: &TargetType) -> Result<(), String> {
+        for tool in [&self.cc, &self.cxx, &self.link_rule(target_type).linker] {
+            if env::program(tool).is_none_or(|program| which::which(program).is_err()) {
+                return Err(format!("{} of the {} toolchain is not in PATH", tool, self.name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn is_toolchain_file(toolchain: &str) -> bool {
+    toolchain.ends_with(".toml") || toolchain.ends_with(".json")
+}
+
+fn expand_args(args: &[String], objects: &[String], source: &Path, output: &Path) -> Vec<String> {
+    let mut expanded = Vec::new();
+
+    for arg in args {
+        if arg == "{objects}" {
+            expanded.extend(objects.iter().cloned());
+        } else {
+            expanded.push(arg
+                .replace("{source}", source.to_str().unwrap())
+                .replace("{output}", output.to_str().unwrap()));
+        }
+    }
+
+    expanded
+}
+
+// fields of a toolchain file replace those of the inherited preset, tables are merged
+fn merge(target: &mut serde_json::Value, value: serde_json::Value) {
+    match (target, value) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(value)) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+#[test]
+fn test_toolchain_presets() {
+    for family in [ToolchainFamily::GNU, ToolchainFamily::LLVM, ToolchainFamily::MSVC] {
+        assert_eq!(family.preset().family, family);
+    }
+
+    let gnu = ToolchainFamily::GNU.preset();
+    assert_eq!(
+        gnu.compile_args(Path::new("src/main.cpp"), Path::new("build/main.o")),
+        ["-c", "src/main.cpp", "-o", "build/main.o"]);
+    assert_eq!(
+        gnu.link_args(&TargetType::StaticLib, &["a.o".to_string(), "b.o".to_string()], Path::new("libx.a")),
+        ["rcs", "libx.a", "a.o", "b.o"]);
+    assert_eq!(gnu.cxx_std_flag(&LanguageStandard::parse("gnu++17").unwrap()), "-std=gnu++17");
+    assert_eq!(gnu.optimization_flags(Optimization::Size), ["-Os"]);
+
+    let zig = Toolchain::preset("zig").unwrap();
+    assert_eq!((zig.family, zig.cxx.as_str(), zig.static_lib.linker.as_str()), (ToolchainFamily::LLVM, "zig c++", "zig ar"));
+    assert_eq!(zig.flags.target.as_deref(), Some("--target={target}"));
+
+    let clang_cl = Toolchain::preset("clang-cl").unwrap();
+    assert_eq!((clang_cl.family, clang_cl.cxx.as_str()), (ToolchainFamily::MSVC, "clang-cl"));
+    assert_eq!(clang_cl.static_lib.linker, "llvm-lib");
+
+    let msvc = ToolchainFamily::MSVC.preset();
+    assert_eq!(msvc.cxx_std_flag(&LanguageStandard::parse("23").unwrap()), "/std:c++latest");
+    assert_eq!(msvc.c_std_flag(&LanguageStandard::parse("gnu99").unwrap()), "/std:c11");
+}
+
+#[test]
+fn test_toolchain_file() {
+    let dir = std::env::temp_dir().join(format!("cxon_toolchain_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("arm-none-eabi.toml");
+    std::fs::write(&path, r#"
+        inherits = "gnu"
+        cc = "arm-none-eabi-gcc"
+        cxx = "arm-none-eabi-g++"
+
+        [executable]
+        linker = "arm-none-eabi-g++"
+        args = ["{objects}", "-o", "{output}", "-specs=nosys.specs"]
+        extension = "elf"
+    "#).unwrap();
+
+    let toolchain = Toolchain::from_config("arm-none-eabi.toml", &dir);
+    assert_eq!(toolchain.name, "arm-none-eabi");
+    assert_eq!(toolchain.family, ToolchainFamily::GNU);
+    assert_eq!(toolchain.cc, "arm-none-eabi-gcc");
+    assert_eq!(toolchain.static_lib.linker, "ar");
+    assert_eq!(
+        toolchain.link_args(&TargetType::Executable, &["main.o".to_string()], Path::new("app.elf")),
+        ["main.o", "-o", "app.elf", "-specs=nosys.specs"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
 }
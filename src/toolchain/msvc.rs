@@ -1,32 +1,220 @@
-use crate::toolchain::ToolChainTrait;
+use std::path::Path;
 
-pub struct MSVC {
-    #[allow(dead_code)] data: ()
+use crate::toolchain::Toolchain;
+
+const PRESET: &str = r#"
+name = "msvc"
+family = "msvc"
+cc = "cl"
+cxx = "cl"
+
+[compile]
+args = ["/nologo", "/c", "{source}", "/Fo{output}"]
+object_extension = "obj"
+
+# the link flags follow /link, see translate_link_flags
+[executable]
+linker = "cl"
+args = ["/nologo", "{objects}", "/Fe{output}"]
+extension = "exe"
+
+[static_lib]
+linker = "lib"
+args = ["/nologo", "/OUT:{output}", "{objects}"]
+extension = "lib"
+
+[shared_lib]
+linker = "link"
+args = ["/nologo", "/DLL", "/OUT:{output}", "{objects}"]
+extension = "dll"
+
+# there are no relocatable links, the objects are bundled in a library
+[object_lib]
+linker = "lib"
+args = ["/nologo", "/OUT:{output}", "{objects}"]
+extension = "lib"
+
+[flags]
+define_prefix = "/D"
+include_prefix = "/I"
+link_dir_prefix = "/LIBPATH:"
+link_lib_prefix = ""
+link_lib_suffix = ".lib"
+# /Z7 keeps the debug information in the objects, with /Zi the parallel
+# cl processes would write to the same pdb file
+debug = "/Z7"
+
+[flags.optimization]
+none = ["/Od"]
+size = ["/O1"]
+speed = ["/O2"]
+max = ["/O2", "/Ob3"]
+
+[flags.warnings]
+none = ["/W0"]
+default = []
+all = ["/W3"]
+extra = ["/W4"]
+error = ["/W4", "/WX"]
+
+# MSVC has no line tables only mode
+[flags.debug_info]
+none = []
+minimal = ["/Z7"]
+full = ["/Z7"]
+
+# MSVC only knows C11 and C17, GNU extensions are ignored
+[flags.c_std]
+flag = "/std:c{version}"
+versions = { "89" = "11", "90" = "11", "99" = "11" }
+
+# C++14 is the oldest standard of MSVC and C++23 is only available as c++latest
+[flags.cxx_std]
+flag = "/std:c++{version}"
+versions = { "98" = "14", "03" = "14", "11" = "14", "23" = "latest", "2b" = "latest", "26" = "latest", "2c" = "latest" }
+"#;
+
+// clang-cl takes the flags of cl, links with lld-link and archives with
+// llvm-lib, so it can build for Windows from other platforms with the
+// sysroot of a Windows SDK
+const CLANG_CL_PRESET: &str = r#"
+name = "clang-cl"
+cc = "clang-cl"
+cxx = "clang-cl"
+
+[executable]
+linker = "lld-link"
+args = ["/nologo", "/OUT:{output}", "{objects}"]
+
+[static_lib]
+linker = "llvm-lib"
+
+[shared_lib]
+linker = "lld-link"
+
+[object_lib]
+linker = "llvm-lib"
+
+[flags]
+time_trace = "/clang:-ftime-trace"
+target = "--target={target}"
+sysroot = "/winsysroot{sysroot}"
+"#;
+
+pub fn toolchain() -> Toolchain {
+    Toolchain::from_toml(PRESET).expect("Invalid msvc toolchain preset")
 }
 
-impl ToolChainTrait for MSVC {
-    const CC:     &'static str = "cl";
-    const CXX:    &'static str = "cl";
-    const DEBUG_FLAG:  &'static str = "/Zi";
-
-    const EXECUTABLE_LINKER: &'static str = "cl";
-    const STATIC_LIB_LINKER: &'static str = "lib";
-    const SHARED_LIB_LINKER: &'static str = "link";
-    const OBJECT_LIB_LINKER: &'static str = "lib";
-
-    const EXECUTABLE_OUTPUT_FLAG: &'static str = "/Fe:";
-    const STATIC_LIB_OUTPUT_FLAG: &'static str = "/OUT:";
-    const SHARED_LIB_OUTPUT_FLAG: &'static str = "/OUT:";
-    const OBJECT_LIB_OUTPUT_FLAG: &'static str = "/Fo:";
-
-    const EXECUTABLE_EXTENSION: &'static str = "exe";
-    const STATIC_LIB_EXTENSION: &'static str = "lib";
-    const SHARED_LIB_EXTENSION: &'static str = "dll";
-    const OBJECT_LIB_EXTENSION: &'static str = "obj";
-    
-    const ONLY_COMPILE_FLAG:    &'static str = "/c";
-    const DEFINE_FLAG_PREFIX:   &'static str = "/D";
-    const INCLUDE_FLAG_PREFIX:  &'static str = "/I";
-    const LINK_DIR_FLAG_PREFIX: &'static str = "/LIBPATH:";
-    const LINK_LIB_FLAG_PREFIX: &'static str = "/LD";
-}
\ No newline at end of file
+pub fn clang_cl() -> Toolchain {
+    toolchain().inherit(CLANG_CL_PRESET).expect("Invalid clang-cl toolchain preset")
+}
+
+// The MSVC tools, which take the link flags differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    // the compiler driver, passing the flags after /link on to the linker
+    Cl,
+    Link,
+    Lib,
+}
+
+impl Tool {
+    // lld-link and llvm-lib take the flags of link and lib
+    pub fn of(program: &str) -> Tool {
+        let stem = Path::new(program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match stem.as_str() {
+            "link" | "lld-link" => Tool::Link,
+            "lib" | "llvm-lib" => Tool::Lib,
+            _ => Tool::Cl,
+        }
+    }
+}
+
+/// Spells link flags for an MSVC tool. GNU style flags, such as those of
+/// LDFLAGS, are translated, the debug flag of the compiler becomes /DEBUG,
+/// and cl gets the flags after /link so that they reach the linker. The
+/// target and sysroot of clang-cl stay with the compiler driver.
+pub fn translate_link_flags(tool: Tool, flags: &[String]) -> Vec<String> {
+    let mut driver = Vec::new();
+    let mut translated = Vec::new();
+
+    for flag in flags {
+        match flag.as_str() {
+            flag if flag.starts_with("--target=") => {
+                if tool == Tool::Cl {
+                    driver.push(flag.to_string());
+                }
+            }
+            flag if flag.starts_with("/winsysroot") => match tool {
+                Tool::Cl => driver.push(flag.to_string()),
+                Tool::Link => translated.push(format!("/winsysroot:{}", flag["/winsysroot".len()..].trim_start_matches(':'))),
+                Tool::Lib => (),
+            },
+            "-g" | "/Zi" | "/Z7" | "/DEBUG" => {
+                // lib has no debug information of its own
+                if tool != Tool::Lib && !translated.iter().any(|flag| flag == "/DEBUG") {
+                    translated.push("/DEBUG".to_string());
+                }
+            }
+            // the shared_lib rule passes /DLL already
+            "-shared" => (),
+            flag if flag.starts_with("-Wl,") => {
+                translated.extend(flag["-Wl,".len()..].split(',').map(str::to_string));
+            }
+            flag if flag.starts_with("-L") => translated.push(format!("/LIBPATH:{}", &flag[2..])),
+            flag if flag.starts_with("-l") => translated.push(format!("{}.lib", &flag[2..])),
+            flag => translated.push(flag.to_string()),
+        }
+    }
+
+    if tool == Tool::Cl && !translated.is_empty() {
+        translated.insert(0, "/link".to_string());
+    }
+
+    [driver, translated].concat()
+}
+
+#[test]
+fn test_msvc_command_lines() {
+    use std::path::PathBuf;
+
+    use crate::toolchain::TargetType;
+
+    let msvc = toolchain();
+    assert_eq!(
+        msvc.compile_args(Path::new("src/main.cpp"), Path::new("build/main.obj")),
+        ["/nologo", "/c", "src/main.cpp", "/Fobuild/main.obj"]);
+    assert_eq!(
+        msvc.link_args(&TargetType::StaticLib, &["a.obj".to_string(), "b.obj".to_string()], Path::new("x.lib")),
+        ["/nologo", "/OUT:x.lib", "a.obj", "b.obj"]);
+    assert_eq!(msvc.link_lib_arg("user32"), "user32.lib");
+    assert_eq!(msvc.link_lib_arg("ws2_32.lib"), "ws2_32.lib");
+    assert_eq!(msvc.link_dir_arg(&PathBuf::from("lib")), "/LIBPATH:lib");
+
+    let flags: Vec<String> = ["/Zi", "-Lvendor", "-lssl", "-Wl,/STACK:8388608", "/LTCG"].map(String::from).into();
+    assert_eq!(translate_link_flags(Tool::of("cl"), &flags),
+        ["/link", "/DEBUG", "/LIBPATH:vendor", "ssl.lib", "/STACK:8388608", "/LTCG"]);
+    assert_eq!(translate_link_flags(Tool::of("C:/VS/bin/link.exe"), &flags),
+        ["/DEBUG", "/LIBPATH:vendor", "ssl.lib", "/STACK:8388608", "/LTCG"]);
+    assert_eq!(translate_link_flags(Tool::of("lib"), &flags[1..3]), ["/LIBPATH:vendor", "ssl.lib"]);
+    assert!(translate_link_flags(Tool::Lib, &["/Zi".to_string()]).is_empty());
+    assert!(translate_link_flags(Tool::Cl, &[]).is_empty());
+    assert_eq!(msvc.flags.debug, "/Z7");
+    assert_eq!(translate_link_flags(Tool::Link, &["/Z7".to_string(), "-shared".to_string()]), ["/DEBUG"]);
+
+    let clang_cl = clang_cl();
+    assert_eq!(clang_cl.compile_args(Path::new("main.cpp"), Path::new("main.obj")), ["/nologo", "/c", "main.cpp", "/Fomain.obj"]);
+    assert_eq!(
+        clang_cl.link_args(&TargetType::Executable, &["main.obj".to_string()], Path::new("app.exe")),
+        ["/nologo", "/OUT:app.exe", "main.obj"]);
+
+    let cross: Vec<String> = ["--target=x86_64-pc-windows-msvc", "/winsysroot/opt/xwin", "/Zi"].map(String::from).into();
+    assert_eq!(translate_link_flags(Tool::of("lld-link"), &cross), ["/winsysroot:/opt/xwin", "/DEBUG"]);
+    assert_eq!(translate_link_flags(Tool::of("clang-cl"), &cross),
+        ["--target=x86_64-pc-windows-msvc", "/winsysroot/opt/xwin", "/link", "/DEBUG"]);
+    assert!(translate_link_flags(Tool::of("llvm-lib"), &cross).is_empty());
+}
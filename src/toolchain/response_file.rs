@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use crate::toolchain::ToolchainFamily;
+
+// Windows limits a command line to 32767 characters and Linux a single
+// argument to 128 KiB, so long lists are moved out well below both
+const MAX_COMMAND_LINE: usize = 8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quoting {
+    // backslash escapes, as read by gcc, ar and clang outside of Windows
+    Gnu,
+    // double quotes, as read by cl, link, lib and clang on Windows
+    Windows,
+}
+
+impl Quoting {
+    pub fn of(family: ToolchainFamily) -> Quoting {
+        match family {
+            ToolchainFamily::MSVC => Quoting::Windows,
+            ToolchainFamily::LLVM if cfg!(windows) => Quoting::Windows,
+            _ => Quoting::Gnu,
+        }
+    }
+}
+
+pub fn is_too_long(command_line: &[String]) -> bool {
+    command_line.iter().map(|arg| arg.len() + 1).sum::<usize>() > MAX_COMMAND_LINE
+}
+
+/// Writes arguments to a response file, one per line, and returns the
+/// `@file` argument replacing them.
+pub fn write(path: &Path, args: &[String], quoting: Quoting) -> String {
+    let content: Vec<String> = args.iter().map(|arg| quote(arg, quoting)).collect();
+
+    std::fs::write(path, content.join("\n") + "\n")
+        .unwrap_or_else(|err| panic!("Failed to write response file {}: {}", path.display(), err));
+
+    format!("@{}", path.display())
+}
+
+fn quote(arg: &str, quoting: Quoting) -> String {
+    match quoting {
+        Quoting::Gnu => {
+            let mut quoted = String::new();
+            for c in arg.chars() {
+                if c.is_whitespace() || matches!(c, '\'' | '"' | '\\') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted
+        }
+        Quoting::Windows if arg.is_empty() || arg.contains([' ', '\t', '"']) => {
+            // backslashes are only special before a quote
+            let mut quoted = String::from('"');
+            let mut backslashes = 0;
+            for c in arg.chars() {
+                match c {
+                    '\\' => backslashes += 1,
+                    // the backslashes before it are doubled and the quote is escaped
+                    '"' => {
+                        quoted.push_str(&"\\".repeat(backslashes + 1));
+                        backslashes = 0;
+                    }
+                    _ => backslashes = 0,
+                }
+                quoted.push(c);
+            }
+            // so are those before the closing quote
+            quoted.push_str(&"\\".repeat(backslashes));
+            quoted.push('"');
+            quoted
+        }
+        Quoting::Windows => arg.to_string(),
+    }
+}
+
+#[test]
+fn test_response_file_quoting() {
+    assert_eq!(quote("build/main.o", Quoting::Gnu), "build/main.o");
+    assert_eq!(quote("-DNAME=\"a b\"", Quoting::Gnu), r#"-DNAME=\"a\ b\""#);
+    assert_eq!(quote(r"C:\src\x.o", Quoting::Gnu), r"C:\\src\\x.o");
+
+    assert_eq!(quote(r"C:\src\x.obj", Quoting::Windows), r"C:\src\x.obj");
+    assert_eq!(quote(r"C:\Program Files\lib\", Quoting::Windows), r#""C:\Program Files\lib\\""#);
+    assert_eq!(quote("/DNAME=\"a b\"", Quoting::Windows), r#""/DNAME=\"a b\"""#);
+
+    assert!(!is_too_long(&["g++".to_string(), "main.o".to_string()]));
+    assert!(is_too_long(&vec!["build/some/long/path/object.o".to_string(); 400]));
+}
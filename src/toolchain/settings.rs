@@ -0,0 +1,123 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Optimization {
+    #[default]
+    None,
+    Size,
+    Speed,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Warnings {
+    None,
+    #[default]
+    Default,
+    All,
+    Extra,
+    // extra warnings treated as errors
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugInfo {
+    #[default]
+    None,
+    // line tables only, enough for backtraces and profilers
+    Minimal,
+    Full,
+}
+
+// `true` and `false` are accepted for the former boolean debug field
+impl<'de> Deserialize<'de> for DebugInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Bool(bool),
+            Text(String),
+        }
+
+        match Value::deserialize(deserializer)? {
+            Value::Bool(true) => Ok(DebugInfo::Full),
+            Value::Bool(false) => Ok(DebugInfo::None),
+            Value::Text(text) => match text.to_lowercase().as_str() {
+                "none" => Ok(DebugInfo::None),
+                "minimal" => Ok(DebugInfo::Minimal),
+                "full" => Ok(DebugInfo::Full),
+                _ => Err(serde::de::Error::custom(format!(
+                    "Unsupported debug_info: {}. Supported levels are: none, minimal, full", text))),
+            },
+        }
+    }
+}
+
+// A language standard such as `17`, `"c++20"`, `"gnu11"` or `"gnu++17"`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LanguageStandard {
+    pub version: String,
+    // GNU extensions, only supported by the gnu and llvm toolchains
+    pub gnu: bool,
+}
+
+impl LanguageStandard {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+
+        let (gnu, rest) = match text.strip_prefix("gnu") {
+            Some(rest) => (true, rest),
+            None => (false, text.as_str()),
+        };
+        let version = rest
+            .strip_prefix("c++")
+            .or_else(|| rest.strip_prefix('c'))
+            .unwrap_or(rest);
+        let version = version.strip_prefix("++").unwrap_or(version);
+
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+
+        Some(Self {
+            version: version.to_string(),
+            gnu,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for LanguageStandard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Number(u32),
+            Text(String),
+        }
+
+        let text = match Value::deserialize(deserializer)? {
+            Value::Number(number) => number.to_string(),
+            Value::Text(text) => text,
+        };
+
+        LanguageStandard::parse(&text).ok_or_else(|| serde::de::Error::custom(format!(
+            "Invalid language standard: {}. Use a version such as 17, \"c++20\" or \"gnu11\"", text)))
+    }
+}
+
+#[test]
+fn test_language_standard() {
+    let std = LanguageStandard::parse("c++20").unwrap();
+    assert_eq!((std.version.as_str(), std.gnu), ("20", false));
+
+    let std = LanguageStandard::parse("gnu++17").unwrap();
+    assert_eq!((std.version.as_str(), std.gnu), ("17", true));
+
+    let std = LanguageStandard::parse("gnu11").unwrap();
+    assert_eq!((std.version.as_str(), std.gnu), ("11", true));
+
+    assert!(LanguageStandard::parse("c++").is_none());
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::toolchain::Toolchain;
+
+// Emscripten, writing the .wasm module next to the .js loader of an executable
+const PRESET: &str = r#"
+name = "wasm"
+family = "llvm"
+cc = "emcc"
+cxx = "em++"
+
+[compile]
+args = ["-c", "{source}", "-o", "{output}"]
+object_extension = "o"
+
+[executable]
+linker = "em++"
+args = ["{objects}", "-o", "{output}"]
+extension = "js"
+runner = ["node"]
+
+[static_lib]
+linker = "emar"
+args = ["rcs", "{output}", "{objects}"]
+extension = "a"
+
+# loaded by the main module with dlopen
+[shared_lib]
+linker = "em++"
+args = ["-sSIDE_MODULE=1", "-o", "{output}", "{objects}"]
+extension = "wasm"
+
+[object_lib]
+linker = "em++"
+args = ["-r", "{objects}", "-o", "{output}"]
+extension = "o"
+
+[flags]
+define_prefix = "-D"
+include_prefix = "-I"
+link_dir_prefix = "-L"
+link_lib_prefix = "-l"
+debug = "-g"
+time_trace = "-ftime-trace"
+link_setting = "-s{name}={value}"
+
+[flags.optimization]
+none = ["-O0"]
+size = ["-Oz"]
+speed = ["-O2"]
+max = ["-O3"]
+
+[flags.warnings]
+none = ["-w"]
+default = []
+all = ["-Wall"]
+extra = ["-Wall", "-Wextra"]
+error = ["-Wall", "-Wextra", "-Werror"]
+
+[flags.debug_info]
+none = []
+minimal = ["-gline-tables-only"]
+full = ["-g"]
+
+[flags.c_std]
+flag = "-std=c{version}"
+gnu_flag = "-std=gnu{version}"
+
+[flags.cxx_std]
+flag = "-std=c++{version}"
+gnu_flag = "-std=gnu++{version}"
+"#;
+
+pub fn toolchain() -> Toolchain {
+    Toolchain::from_toml(PRESET).expect("Invalid wasm toolchain preset")
+}
+
+// The `wasm` section of cxon.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WasmConfig {
+    #[serde(default)]
+    pub output: WasmOutput,
+    // such as "EXPORTED_FUNCTIONS": ["_main", "_add"], passed when linking
+    #[serde(default)]
+    pub settings: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WasmOutput {
+    // a loader for node and bundlers
+    #[default]
+    Js,
+    // a page loading the program in a browser
+    Html,
+}
+
+impl WasmConfig {
+    pub fn extension(&self) -> &'static str {
+        match self.output {
+            WasmOutput::Js => "js",
+            WasmOutput::Html => "html",
+        }
+    }
+
+    // emrun serves the page and opens a browser, node of the toolchain runs the js loader
+    pub fn runner(&self) -> Option<Vec<String>> {
+        match self.output {
+            WasmOutput::Js => None,
+            WasmOutput::Html => Some(vec!["emrun".to_string()]),
+        }
+    }
+
+    /// The settings as link flags. Lists are joined with commas and
+    /// booleans become 1 or 0, as `-sEXPORTED_FUNCTIONS=_main,_add`.
+    pub fn setting_args(&self, toolchain: &Toolchain) -> Vec<String> {
+        let Some(template) = &toolchain.flags.link_setting else {
+            return Vec::new();
+        };
+
+        self.settings
+            .iter()
+            .map(|(name, value)| template
+                .replace("{name}", name)
+                .replace("{value}", &setting_value(value)))
+            .collect()
+    }
+}
+
+fn setting_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Bool(value) => if *value { "1" } else { "0" }.to_string(),
+        serde_json::Value::String(value) => value.clone(),
+        serde_json::Value::Array(values) => values.iter().map(setting_value).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    }
+}
+
+#[test]
+fn test_wasm_settings() {
+    let config: WasmConfig = serde_json::from_str(r#"{
+        "output": "html",
+        "settings": { "EXPORTED_FUNCTIONS": ["_main", "_add"], "ALLOW_MEMORY_GROWTH": true, "INITIAL_MEMORY": 33554432 }
+    }"#).unwrap();
+
+    let wasm = toolchain();
+    assert_eq!(config.extension(), "html");
+    assert_eq!(config.setting_args(&wasm),
+        ["-sALLOW_MEMORY_GROWTH=1", "-sEXPORTED_FUNCTIONS=_main,_add", "-sINITIAL_MEMORY=33554432"]);
+    assert!(config.setting_args(&crate::toolchain::ToolchainFamily::GNU.preset()).is_empty());
+    assert_eq!(config.runner(), Some(vec!["emrun".to_string()]));
+    assert_eq!(wasm.executable.runner, ["node"]);
+}
@@ -0,0 +1,26 @@
+use crate::toolchain::{Toolchain, llvm};
+
+// zig bundles clang with the libc headers and libraries of many targets, so
+// `target` cross compiles without a sysroot
+const PRESET: &str = r#"
+name = "zig"
+cc = "zig cc"
+cxx = "zig c++"
+
+[executable]
+linker = "zig c++"
+
+[static_lib]
+linker = "zig ar"
+
+[shared_lib]
+linker = "zig c++"
+
+[object_lib]
+linker = "zig c++"
+args = ["-r", "{objects}", "-o", "{output}"]
+"#;
+
+pub fn toolchain() -> Toolchain {
+    llvm::toolchain().inherit(PRESET).expect("Invalid zig toolchain preset")
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+// A parse error with the line and column in the original JSONC text
+#[derive(Debug)]
+pub struct JsoncError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsoncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {} column {}", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for JsoncError {}
+
+/// Parses JSON with the JSON5 extensions people write in config files:
+/// `//` and `/* */` comments, trailing commas and unquoted object keys.
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, JsoncError> {
+    let converted = Converter::new(text).convert();
+
+    serde_json::from_str(&converted.json).map_err(|err| {
+        let message = err.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(index) => message[..index].to_string(),
+            None => message,
+        };
+
+        JsoncError {
+            line: err.line(),
+            column: converted.original_column(err.line(), err.column()),
+            message,
+        }
+    })
+}
+
+struct Converted {
+    json: String,
+    // quotes inserted around unquoted keys, as (line, column in the json) for every quote
+    insertions: Vec<(usize, usize)>,
+}
+
+impl Converted {
+    fn original_column(&self, line: usize, column: usize) -> usize {
+        let inserted = self.insertions
+            .iter()
+            .filter(|(l, c)| *l == line && *c < column)
+            .count();
+
+        column - inserted
+    }
+}
+
+#[derive(PartialEq)]
+enum Container {
+    Object,
+    Array,
+}
+
+struct Converter<'a> {
+    text: &'a str,
+    pos: usize,
+    out: String,
+    insertions: Vec<(usize, usize)>,
+
+    line: usize,
+    // bytes written to the current line of the output
+    column: usize,
+
+    stack: Vec<Container>,
+    expect_key: bool,
+}
+
+impl<'a> Converter<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            pos: 0,
+            out: String::with_capacity(text.len()),
+            insertions: Vec::new(),
+            line: 1,
+            column: 0,
+            stack: Vec::new(),
+            expect_key: false,
+        }
+    }
+
+    fn convert(mut self) -> Converted {
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => {
+                    self.expect_key = false;
+                    self.copy_string();
+                }
+                '/' if self.starts_with("//") || self.starts_with("/*") => {
+                    let len = self.comment_len(self.pos);
+                    self.blank(len);
+                }
+                ',' if self.closes_after(self.pos + 1) => {
+                    // trailing comma
+                    self.blank(1);
+                }
+                '{' | '[' => {
+                    self.stack.push(if c == '{' { Container::Object } else { Container::Array });
+                    self.expect_key = c == '{';
+                    self.copy(1);
+                }
+                '}' | ']' => {
+                    self.stack.pop();
+                    self.expect_key = false;
+                    self.copy(1);
+                }
+                ',' => {
+                    self.expect_key = self.stack.last() == Some(&Container::Object);
+                    self.copy(1);
+                }
+                c if self.expect_key && (c.is_alphabetic() || c == '_' || c == '$') => {
+                    self.expect_key = false;
+                    self.quote_key();
+                }
+                c if c.is_whitespace() => self.copy(c.len_utf8()),
+                c => {
+                    self.expect_key = false;
+                    self.copy(c.len_utf8());
+                }
+            }
+        }
+
+        Converted {
+            json: self.out,
+            insertions: self.insertions,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        self.text[self.pos..].starts_with(pattern)
+    }
+
+    fn push(&mut self, s: &str) {
+        for c in s.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += c.len_utf8();
+            }
+        }
+        self.out.push_str(s);
+    }
+
+    fn copy(&mut self, len: usize) {
+        let text = self.text;
+        self.push(&text[self.pos..self.pos + len]);
+        self.pos += len;
+    }
+
+    // replaces the text with spaces of the same size, keeping line breaks
+    fn blank(&mut self, len: usize) {
+        let text = self.text;
+        for c in text[self.pos..self.pos + len].chars() {
+            if c == '\n' {
+                self.push("\n");
+            } else {
+                self.push(&" ".repeat(c.len_utf8()));
+            }
+        }
+        self.pos += len;
+    }
+
+    fn copy_string(&mut self) {
+        let bytes = self.text.as_bytes();
+        let mut end = self.pos + 1;
+
+        while end < bytes.len() && bytes[end] != b'"' {
+            end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
+
+        self.copy((end + 1).min(bytes.len()) - self.pos);
+    }
+
+    fn quote_key(&mut self) {
+        let len = self.text[self.pos..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(self.text.len() - self.pos);
+
+        self.insertions.push((self.line, self.column + 1));
+        self.push("\"");
+        self.copy(len);
+        self.insertions.push((self.line, self.column + 1));
+        self.push("\"");
+    }
+
+    fn comment_len(&self, start: usize) -> usize {
+        let rest = &self.text[start..];
+
+        if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            rest[2..].find("*/").map(|end| end + 4).unwrap_or(rest.len())
+        }
+    }
+
+    // true if only whitespace and comments are left before a closing bracket
+    fn closes_after(&self, start: usize) -> bool {
+        let mut pos = start;
+
+        while pos < self.text.len() {
+            let rest = &self.text[pos..];
+            let c = rest.chars().next().unwrap();
+
+            if c.is_whitespace() {
+                pos += c.len_utf8();
+            } else if rest.starts_with("//") || rest.starts_with("/*") {
+                pos += self.comment_len(pos);
+            } else {
+                return c == '}' || c == ']';
+            }
+        }
+
+        false
+    }
+}
+
+#[test]
+fn test_jsonc_extensions() {
+    let value: serde_json::Value = from_str(r#"{
+        // line comment
+        project: "demo", /* block
+        comment */
+        "flags": ["-Wall", "-Wextra",],
+        $schema: "https://example.com/x.json",
+    }"#).unwrap();
+
+    assert_eq!(value["project"], "demo");
+    assert_eq!(value["flags"], serde_json::json!(["-Wall", "-Wextra"]));
+    assert_eq!(value["$schema"], "https://example.com/x.json");
+}
+
+#[test]
+fn test_jsonc_error_position() {
+    let err = from_str::<serde_json::Value>("{\n  project: \"demo\" \"x\"\n}").unwrap_err();
+
+    // `"proje"` takes the same room as the unquoted `project`
+    let expected = serde_json::from_str::<serde_json::Value>("{\n  \"proje\": \"demo\" \"x\"\n}").unwrap_err();
+    assert_eq!((err.line, err.column), (expected.line(), expected.column()));
+}
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use crate::{cli::arg, cxon::get_cxon_config, object::source::Source, toolchain::ToolChainTrait};
+pub mod jsonc;
+
+use crate::{cli::arg, cxon::get_cxon_config, object::source::Source, toolchain::Toolchain};
 
 pub fn normalize_and_canonicalize_path(path: PathBuf) -> PathBuf {
     let canonicalized_path = if !path.is_absolute() {
@@ -26,7 +28,7 @@ fn normalize_path(path: PathBuf) -> PathBuf {
     }
 }
 
-pub fn get_object_target_path<T: ToolChainTrait>(src: &Source) -> Result<PathBuf, String> {
+pub fn get_object_target_path(src: &Source, toolchain: &Toolchain) -> Result<PathBuf, String> {
     let src_path = src.get_path();
 
     let obj_sub_path = pathdiff::diff_paths(&src_path, arg::get_args().project_dir);
@@ -41,7 +43,22 @@ pub fn get_object_target_path<T: ToolChainTrait>(src: &Source) -> Result<PathBuf
         std::fs::create_dir_all(obj_path.parent().unwrap()).expect("Failed to create object file directory");
     }
 
-    Ok(obj_path.with_extension(T::OBJECT_LIB_EXTENSION))
+    Ok(obj_path.with_extension(&toolchain.compile.object_extension))
+}
+
+// directory for the metadata cxon keeps between builds
+pub fn get_cxon_cache_dir() -> PathBuf {
+    let dir = get_cxon_config()
+        .read()
+        .unwrap()
+        .build_dir
+        .join(".cxon");
+
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir).expect("Failed to create cxon cache directory");
+    }
+
+    dir
 }
 
 pub fn check_executable_exists(executable: &str) -> String {
@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Variables usable as `${name}` in cxon.json: the built-in ones such as
+/// `project_dir`, the user-defined `vars`, and `${env:NAME}` for environment
+/// variables. `$$` is a literal `$`.
+pub struct Variables {
+    builtin: HashMap<String, String>,
+    user: BTreeMap<String, String>,
+}
+
+impl Variables {
+    pub fn new(builtin: HashMap<String, String>, user: BTreeMap<String, String>) -> Self {
+        for name in user.keys() {
+            if builtin.contains_key(name) || name.starts_with("env:") {
+                panic!("Variable {} of vars conflicts with a built-in variable", name);
+            }
+        }
+
+        Self { builtin, user }
+    }
+
+    pub fn expand(&self, text: &str) -> String {
+        self.expand_with(text, &mut Vec::new())
+    }
+
+    fn expand_with(&self, text: &str, resolving: &mut Vec<String>) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(index) = rest.find('$') {
+            result.push_str(&rest[..index]);
+            rest = &rest[index..];
+
+            if let Some(after) = rest.strip_prefix("$$") {
+                result.push('$');
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("${") {
+                let Some(end) = after.find('}') else {
+                    panic!("Unterminated variable in {}", text);
+                };
+
+                result.push_str(&self.lookup(&after[..end], resolving));
+                rest = &after[end + 1..];
+            } else {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+
+        result.push_str(rest);
+        result
+    }
+
+    fn lookup(&self, name: &str, resolving: &mut Vec<String>) -> String {
+        let name = name.trim();
+
+        if let Some(env) = name.strip_prefix("env:") {
+            return std::env::var(env)
+                .unwrap_or_else(|_| panic!("Environment variable {} used in cxon.json is not set", env));
+        }
+
+        if let Some(value) = self.builtin.get(name) {
+            return value.clone();
+        }
+
+        let Some(value) = self.user.get(name) else {
+            panic!("Unknown variable ${{{}}}. Define it in vars, or use one of: {}, env:NAME",
+                name, self.builtin_names().join(", "));
+        };
+
+        if resolving.iter().any(|n| n == name) {
+            panic!("Variable {} refers to itself", name);
+        }
+
+        resolving.push(name.to_string());
+        let value = self.expand_with(value, resolving);
+        resolving.pop();
+
+        value
+    }
+
+    fn builtin_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.builtin.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+#[test]
+fn test_expand_variables() {
+    let builtin = HashMap::from([("project_dir".to_string(), "/work/app".to_string())]);
+    let user = BTreeMap::from([
+        ("sdk".to_string(), "${project_dir}/sdk".to_string()),
+        ("sdk_include".to_string(), "${sdk}/include".to_string()),
+    ]);
+    let vars = Variables::new(builtin, user);
+
+    assert_eq!(vars.expand("-I${sdk_include}"), "-I/work/app/sdk/include");
+    assert_eq!(vars.expand("cost=$$5 $HOME"), "cost=$5 $HOME");
+    assert_eq!(vars.expand("${env:PATH}"), std::env::var("PATH").unwrap());
+}